@@ -0,0 +1,597 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : builder.rs
+
+Copyright (C) 2021 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module defines the Logger Builder, which collects all configuration
+    options for a logger instance before spawning its logging thread.
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use std::env;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::sink::{SinkEntry, ENTRY_TIMESTAMP_FORMAT};
+#[cfg(target_os = "linux")]
+use crate::ReopenSignal;
+use crate::{
+    DurabilityPolicy, Level, LevelMask, MtLogger, MtLoggerError, MtLoggerGuard, OutputStream,
+    OverflowPolicy, RotationInterval, RotationStyle, Sink, SinkId, INSTANCE,
+};
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Named Constants
+///////////////////////////////////////////////////////////////////////////////
+
+/// Default buffer size of the channel for sending log messages
+const DEFAULT_CHANNEL_CAPACITY: usize = 512;
+
+/// Default logfile directory location
+const DEFAULT_LOGFILE_DIR: &str = "logs";
+
+/// Default logfile name template
+const DEFAULT_FILE_NAME_TEMPLATE: &str = "{prefix}_{timestamp}.log";
+
+/// Default name of the logging thread
+const DEFAULT_THREAD_NAME: &str = "log_receiver";
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+/// Collects configuration options for a logger instance.
+///
+/// All options besides the logfile prefix have defaults matching the behavior of [`mt_new!`],
+/// so only the options that differ need to be specified.
+///
+/// # Examples
+///
+/// Install a global logger instance that writes uncolored `Debug`-level messages and higher to
+/// `/tmp/my_app/my_app.log`.
+/// ```
+/// # use mt_logger::{Level, MtLoggerBuilder};
+/// MtLoggerBuilder::new("my_app")
+///     .output_level(Level::Debug)
+///     .log_dir("/tmp/my_app")
+///     .file_name_template("{prefix}.log")
+///     .colors(false)
+///     .install();
+/// ```
+#[derive(Debug)]
+pub struct MtLoggerBuilder {
+    pub(crate) logfile_prefix: String,
+    pub(crate) output_mask: LevelMask,
+    pub(crate) target_masks: Vec<(String, LevelMask)>,
+    pub(crate) directives: Option<String>,
+    pub(crate) output_stream: OutputStream,
+    pub(crate) stdout_mask: LevelMask,
+    pub(crate) file_mask: LevelMask,
+    pub(crate) stderr_mask: LevelMask,
+    pub(crate) sinks: Vec<SinkEntry>,
+    pub(crate) log_dir: PathBuf,
+    pub(crate) run_dir_template: Option<String>,
+    pub(crate) append: bool,
+    pub(crate) latest_link: Option<String>,
+    #[cfg(target_os = "linux")]
+    pub(crate) reopen_signal: Option<ReopenSignal>,
+    pub(crate) file_name_template: String,
+    pub(crate) rotation_size: Option<u64>,
+    pub(crate) rotation_style: RotationStyle,
+    pub(crate) rotation_interval: Option<RotationInterval>,
+    pub(crate) rotation_utc: bool,
+    pub(crate) max_logfiles: Option<usize>,
+    pub(crate) max_logfile_age: Option<Duration>,
+    pub(crate) max_total_logfile_size: Option<u64>,
+    #[cfg(feature = "gzip")]
+    pub(crate) compress_rotated: bool,
+    pub(crate) channel_capacity: usize,
+    pub(crate) overflow_policy: OverflowPolicy,
+    pub(crate) durability_policy: DurabilityPolicy,
+    pub(crate) timestamp_format: String,
+    pub(crate) colors: bool,
+    pub(crate) thread_info: bool,
+    pub(crate) source_location: bool,
+    pub(crate) module_path: bool,
+    pub(crate) thread_name: String,
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Object Implementation
+///////////////////////////////////////////////////////////////////////////////
+
+impl MtLoggerBuilder {
+    /// Creates a builder with the given logfile prefix and default values for all other options.
+    pub fn new(logfile_prefix: &str) -> Self {
+        Self {
+            logfile_prefix: logfile_prefix.to_string(),
+            output_mask: LevelMask::at_or_above(Level::Info),
+            target_masks: Vec::new(),
+            directives: None,
+            output_stream: OutputStream::Both,
+            stdout_mask: LevelMask::all(),
+            file_mask: LevelMask::all(),
+            stderr_mask: LevelMask::all(),
+            sinks: Vec::new(),
+            log_dir: PathBuf::from(DEFAULT_LOGFILE_DIR),
+            run_dir_template: None,
+            append: false,
+            latest_link: None,
+            #[cfg(target_os = "linux")]
+            reopen_signal: None,
+            file_name_template: DEFAULT_FILE_NAME_TEMPLATE.to_string(),
+            rotation_size: None,
+            rotation_style: RotationStyle::Indexed,
+            rotation_interval: None,
+            rotation_utc: false,
+            max_logfiles: None,
+            max_logfile_age: None,
+            max_total_logfile_size: None,
+            #[cfg(feature = "gzip")]
+            compress_rotated: false,
+            channel_capacity: DEFAULT_CHANNEL_CAPACITY,
+            overflow_policy: OverflowPolicy::Block,
+            durability_policy: DurabilityPolicy::Never,
+            timestamp_format: ENTRY_TIMESTAMP_FORMAT.to_string(),
+            colors: true,
+            thread_info: false,
+            source_location: false,
+            module_path: false,
+            thread_name: DEFAULT_THREAD_NAME.to_string(),
+        }
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Builder Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Sets the minimum [`Level`] of messages to be logged. Defaults to `Level::Info`.
+    pub fn output_level(mut self, output_level: Level) -> Self {
+        self.output_mask = LevelMask::at_or_above(output_level);
+        self
+    }
+
+    /// Sets the exact set of [`Level`]s to be logged, e.g., `Level::Trace | Level::Error`.
+    /// Replaces any minimum level set with [`MtLoggerBuilder::output_level`].
+    pub fn output_level_mask(mut self, output_mask: LevelMask) -> Self {
+        self.output_mask = output_mask;
+        self
+    }
+
+    /// Sets the minimum [`Level`] of messages to be logged for the given target and any targets
+    /// nested under it, e.g., `my_app::net` also applies to `my_app::net::tcp`. Function names are
+    /// matched as well, e.g., `my_app::net::connect`. May be called multiple times, and the most
+    /// specific matching target takes precedence.
+    pub fn target_level(mut self, target: &str, output_level: Level) -> Self {
+        self.target_masks
+            .push((target.to_string(), LevelMask::at_or_above(output_level)));
+        self
+    }
+
+    /// Sets the exact set of [`Level`]s to be logged for the given target, matched as in
+    /// [`MtLoggerBuilder::target_level`].
+    pub fn target_level_mask(mut self, target: &str, output_mask: LevelMask) -> Self {
+        self.target_masks.push((target.to_string(), output_mask));
+        self
+    }
+
+    /// Sets filter directives to apply on top of the output and target levels, e.g.,
+    /// `info,my_app::net=trace,my_app::db=warning`.
+    ///
+    /// Directives are comma-separated, and are either a bare [`Level`] name, which sets the output
+    /// level, or `target=level`, which sets the level for a target as in
+    /// [`MtLoggerBuilder::target_level`]. Targets are matched against both the message target and
+    /// the name of the function that logged it. Level names are case-insensitive.
+    ///
+    /// Directives are parsed when the logger is built, and any invalid directive is reported as
+    /// [`MtLoggerError::InvalidDirective`].
+    pub fn directives(mut self, directives: &str) -> Self {
+        self.directives = Some(directives.to_string());
+        self
+    }
+
+    /// Sets filter directives from the given environment variable, if it is set. See
+    /// [`MtLoggerBuilder::directives`] for the format.
+    ///
+    /// The initialization macros, e.g., [`mt_new!`], read directives from the variable named by
+    /// [`DIRECTIVES_ENV_VAR`](crate::DIRECTIVES_ENV_VAR).
+    pub fn env_directives(self, var_name: &str) -> Self {
+        match env::var(var_name) {
+            Ok(directives) => self.directives(&directives),
+            Err(_) => self,
+        }
+    }
+
+    /// Sets the [`OutputStream`] messages will be written to. Defaults to `OutputStream::Both`.
+    pub fn output_stream(mut self, output_stream: OutputStream) -> Self {
+        self.output_stream = output_stream;
+        self
+    }
+
+    /// Sets the minimum [`Level`] of messages written to the given stream(s), in addition to the
+    /// output and target levels. Defaults to `Level::Trace`, i.e., no additional filtering.
+    ///
+    /// For example, combined with an output level of `Level::Trace`, a `Level::Warning` threshold
+    /// for `OutputStream::StdOut` keeps the full history in the logfile while only showing
+    /// warnings and higher on the console.
+    pub fn stream_level(self, output_stream: OutputStream, output_level: Level) -> Self {
+        self.stream_level_mask(output_stream, LevelMask::at_or_above(output_level))
+    }
+
+    /// Sets the exact set of [`Level`]s written to the given stream(s), in addition to the output
+    /// and target levels. See [`MtLoggerBuilder::stream_level`].
+    pub fn stream_level_mask(
+        mut self,
+        output_stream: OutputStream,
+        output_mask: LevelMask,
+    ) -> Self {
+        for sink_id in output_stream.sink_ids() {
+            self = self.sink_level_mask(sink_id, output_mask);
+        }
+        self
+    }
+
+    /// Registers a [`Sink`] that messages will be written to in addition to the built-in console
+    /// and file sinks. Registering a sink with the ID of a previously-registered sink replaces it.
+    ///
+    /// The ID can later be used to configure or remove the sink, see [`mt_remove_sink!`].
+    pub fn sink<S: Sink + 'static>(mut self, sink_id: SinkId, sink: S) -> Self {
+        self.sinks.retain(|entry| entry.id != sink_id);
+        self.sinks.push(SinkEntry {
+            id: sink_id,
+            sink: Box::new(sink),
+            level_mask: LevelMask::all(),
+        });
+        self
+    }
+
+    /// Sets the exact set of [`Level`]s written to the sink with the given ID, in addition to the
+    /// output and target levels. Applies to sinks registered with [`MtLoggerBuilder::sink`], as
+    /// well as the built-in sinks.
+    pub fn sink_level_mask(mut self, sink_id: SinkId, output_mask: LevelMask) -> Self {
+        match sink_id {
+            SinkId::CONSOLE => self.stdout_mask = output_mask,
+            SinkId::FILE => self.file_mask = output_mask,
+            SinkId::STDERR => self.stderr_mask = output_mask,
+            _ => {
+                if let Some(entry) = self.sinks.iter_mut().find(|entry| entry.id == sink_id) {
+                    entry.level_mask = output_mask;
+                }
+            }
+        }
+        self
+    }
+
+    /// Sets the directory logfiles will be created in, which may be absolute or relative to the
+    /// current directory. Any missing directories are created. Defaults to `logs`.
+    pub fn log_dir<P: Into<PathBuf>>(mut self, log_dir: P) -> Self {
+        self.log_dir = log_dir.into();
+        self
+    }
+
+    /// Sets the template for a per-run subdirectory of the log directory that the logfile will be
+    /// created in, e.g., `{date}` for `logs/2021-06-27/`. Supports the same placeholders as
+    /// [`MtLoggerBuilder::file_name_template`], and may contain further nested directories.
+    /// Defaults to none, i.e., logfiles are created directly in the log directory.
+    pub fn run_dir_template(mut self, run_dir_template: &str) -> Self {
+        self.run_dir_template = Some(run_dir_template.to_string());
+        self
+    }
+
+    /// Sets the template used to name the logfile. Defaults to `{prefix}_{timestamp}.log`.
+    ///
+    /// The following placeholders are replaced:
+    /// * `{prefix}` with the logfile prefix
+    /// * `{timestamp}` with the logger's start time in the ISO 8601-like format described in the
+    ///   module-level documentation
+    /// * `{date}` with the logger's start date, e.g., `2021-06-27`
+    /// * `{pid}` with the ID of the current process
+    /// * `{hostname}` with the name of the host the process is running on
+    pub fn file_name_template(mut self, file_name_template: &str) -> Self {
+        self.file_name_template = file_name_template.to_string();
+        self
+    }
+
+    /// Enables or disables appending to an existing logfile rather than truncating it. Defaults
+    /// to `false`.
+    ///
+    /// Combined with a fixed name template such as `{prefix}.log`, every run writes to the same
+    /// logfile, so that tools like `tail -F` can follow it. A separator marking the start of each
+    /// run is written when the logfile is opened.
+    pub fn append(mut self, append: bool) -> Self {
+        self.append = append;
+        self
+    }
+
+    /// Maintains a symbolic link with the given name in the log directory, e.g., `latest.log`,
+    /// pointing at the active logfile. The link is updated when the logfile is created and after
+    /// each rotation. Defaults to none.
+    pub fn latest_link(mut self, link_name: &str) -> Self {
+        self.latest_link = Some(link_name.to_string());
+        self
+    }
+
+    /// Reopens the logfile whenever the process receives the given signal, as if by
+    /// [`mt_reopen_files!`]. Defaults to none.
+    ///
+    /// This lets an external tool such as logrotate rename the logfile and signal the process,
+    /// rather than copying and truncating the logfile. The logfile is reopened within a second of
+    /// the signal arriving. The previous handler for the signal is replaced when the logger is
+    /// built, and restored once it and every other logger reopening on the signal have shut down,
+    /// e.g., via [`mt_shutdown!`] or [`mt_replace!`].
+    #[cfg(target_os = "linux")]
+    pub fn reopen_on_signal(mut self, reopen_signal: ReopenSignal) -> Self {
+        self.reopen_signal = Some(reopen_signal);
+        self
+    }
+
+    /// Rotates the logfile before writing a message that would grow it beyond the given size in
+    /// bytes. Messages are never split across logfiles, so a single message larger than the limit
+    /// is written to a logfile of its own. Defaults to none, i.e., the logfile grows without bound.
+    pub fn rotation_size(mut self, max_size: u64) -> Self {
+        self.rotation_size = Some(max_size);
+        self
+    }
+
+    /// Rotates the logfile before writing the first message timestamped in a new period of the
+    /// given [`RotationInterval`], e.g., `RotationInterval::Daily` for one logfile per day.
+    /// May be combined with [`MtLoggerBuilder::rotation_size`]. Defaults to none.
+    pub fn rotation_interval(mut self, rotation_interval: RotationInterval) -> Self {
+        self.rotation_interval = Some(rotation_interval);
+        self
+    }
+
+    /// Determines whether rotation periods start on UTC boundaries rather than local time
+    /// boundaries, e.g., at midnight UTC for `RotationInterval::Daily`. Defaults to `false`.
+    pub fn rotation_utc(mut self, rotation_utc: bool) -> Self {
+        self.rotation_utc = rotation_utc;
+        self
+    }
+
+    /// Sets the [`RotationStyle`] used when the logfile is rotated. Defaults to
+    /// `RotationStyle::Indexed`.
+    pub fn rotation_style(mut self, rotation_style: RotationStyle) -> Self {
+        self.rotation_style = rotation_style;
+        self
+    }
+
+    /// Keeps at most the given number of logfiles, including the active logfile, by deleting the
+    /// oldest. Defaults to none, i.e., logfiles are never deleted.
+    ///
+    /// Retention limits are enforced when the logger starts and after each rotation, and only
+    /// apply to logfiles matching the logfile prefix and name templates, including rotated
+    /// logfiles. Placeholders only match text of the shape they expand to, e.g., digits for
+    /// `{pid}`, so logfiles of a logger whose prefix merely starts with this one are left alone.
+    /// The active logfile is never deleted.
+    pub fn max_logfiles(mut self, max_logfiles: usize) -> Self {
+        self.max_logfiles = Some(max_logfiles);
+        self
+    }
+
+    /// Deletes logfiles last modified longer ago than the given age. See
+    /// [`MtLoggerBuilder::max_logfiles`]. Defaults to none.
+    pub fn max_logfile_age(mut self, max_logfile_age: Duration) -> Self {
+        self.max_logfile_age = Some(max_logfile_age);
+        self
+    }
+
+    /// Deletes the oldest logfiles until the total size of all logfiles, including the active
+    /// logfile, is at most the given number of bytes. See [`MtLoggerBuilder::max_logfiles`].
+    /// Defaults to none.
+    pub fn max_total_logfile_size(mut self, max_total_logfile_size: u64) -> Self {
+        self.max_total_logfile_size = Some(max_total_logfile_size);
+        self
+    }
+
+    /// Enables or disables compressing rotated logfiles with gzip, appending `.gz` to their names.
+    /// Compression happens on a separate thread, so it never delays recording messages. Retention
+    /// limits apply to compressed logfiles as well. Defaults to `false`.
+    ///
+    /// Only has an effect if rotation is enabled. Requires the `gzip` feature.
+    #[cfg(feature = "gzip")]
+    pub fn compress_rotated(mut self, compress_rotated: bool) -> Self {
+        self.compress_rotated = compress_rotated;
+        self
+    }
+
+    /// Sets the number of messages that may be queued for the logging thread by each thread that
+    /// logs messages. Defaults to 512.
+    ///
+    /// This is a per-thread limit rather than a bound on the whole channel, as each logging
+    /// thread has its own queue. The total number of queued messages may therefore reach this
+    /// capacity times the number of threads that have logged a message.
+    ///
+    /// Each thread's queue is allocated in full the first time it logs a message, at 56 bytes
+    /// per message on 64-bit targets, or about 28 KiB per thread at the default capacity. Queued
+    /// messages additionally hold their text and metadata until the logging thread processes them.
+    pub fn channel_capacity(mut self, channel_capacity: usize) -> Self {
+        self.channel_capacity = channel_capacity;
+        self
+    }
+
+    /// Sets the [`OverflowPolicy`] applied to messages logged while the calling thread's queue is
+    /// full, see [`MtLoggerBuilder::channel_capacity`].
+    /// Defaults to `OverflowPolicy::Block`.
+    ///
+    /// Any dropped messages are periodically reported by the logging thread as a
+    /// `Warning`-level message.
+    pub fn overflow_policy(mut self, overflow_policy: OverflowPolicy) -> Self {
+        self.overflow_policy = overflow_policy;
+        self
+    }
+
+    /// Sets the [`DurabilityPolicy`] determining when the logfile is synced to disk.
+    /// Defaults to `DurabilityPolicy::Never`.
+    pub fn durability_policy(mut self, durability_policy: DurabilityPolicy) -> Self {
+        self.durability_policy = durability_policy;
+        self
+    }
+
+    /// Sets the [`chrono` format string](chrono::format::strftime) used for message timestamps.
+    /// Defaults to `%Y-%m-%dT%T%.9f`.
+    pub fn timestamp_format(mut self, timestamp_format: &str) -> Self {
+        self.timestamp_format = timestamp_format.to_string();
+        self
+    }
+
+    /// Enables or disables ANSI colors on console output. Defaults to `true`.
+    pub fn colors(mut self, colors: bool) -> Self {
+        self.colors = colors;
+        self
+    }
+
+    /// Enables or disables rendering the name and ID of the thread that logged each message, e.g.,
+    /// `on thread 'main' (ThreadId(1))`. Defaults to `false`.
+    pub fn thread_info(mut self, thread_info: bool) -> Self {
+        self.thread_info = thread_info;
+        self
+    }
+
+    /// Enables or disables rendering the source file, line, and column of each message, e.g.,
+    /// `at src/main.rs:12:5`. Defaults to `false`.
+    pub fn source_location(mut self, source_location: bool) -> Self {
+        self.source_location = source_location;
+        self
+    }
+
+    /// Enables or disables rendering the module path of each message, e.g., `in my_app::net`.
+    /// Defaults to `false`.
+    pub fn module_path(mut self, module_path: bool) -> Self {
+        self.module_path = module_path;
+        self
+    }
+
+    /// Sets the name of the logging thread. Defaults to `log_receiver`.
+    pub fn thread_name(mut self, thread_name: &str) -> Self {
+        self.thread_name = thread_name.to_string();
+        self
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Utility Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Spawns the logging thread and returns a logger instance that sends to it.
+    ///
+    /// The instance is _not_ installed globally, so the logging macros will not use it.
+    /// See [`MtLoggerBuilder::install`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the logfile or logging thread cannot be created, see
+    /// [`MtLoggerBuilder::try_build`] for a fallible version.
+    pub fn build(self) -> MtLogger {
+        self.try_build()
+            .unwrap_or_else(|err| panic!("Failed to build MtLogger: {}", err))
+    }
+
+    /// Spawns the logging thread and returns a logger instance that sends to it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MtLoggerError::InvalidDirective`] if the filter directives cannot be parsed, or
+    /// [`MtLoggerError::Io`] if the log directory, logfile, or logging thread cannot be created.
+    pub fn try_build(self) -> Result<MtLogger, MtLoggerError> {
+        MtLogger::from_builder(self)
+    }
+
+    /// Spawns the logging thread and installs the logger as the global instance used by the
+    /// logging macros.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the global instance has already been initialized, or if the logger cannot be
+    /// built. See [`MtLoggerBuilder::try_install`] for a fallible version.
+    pub fn install(self) {
+        self.try_install()
+            .unwrap_or_else(|err| panic!("Failed to install MtLogger: {}", err))
+    }
+
+    /// Spawns the logging thread and installs the logger as the global instance used by the
+    /// logging macros.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MtLoggerError::AlreadyInitialized`] if the global instance has already been
+    /// initialized, or any error returned by [`MtLoggerBuilder::try_build`].
+    pub fn try_install(self) -> Result<(), MtLoggerError> {
+        // Check first to avoid creating a logfile for an instance that can't be installed
+        if INSTANCE.get().is_some() {
+            return Err(MtLoggerError::AlreadyInitialized);
+        }
+
+        INSTANCE
+            .set(self.try_build()?)
+            .map_err(|_| MtLoggerError::AlreadyInitialized)
+    }
+
+    /// Installs the logger as the global instance, returning an [`MtLoggerGuard`] that flushes
+    /// it when dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`MtLoggerBuilder::install`]. See
+    /// [`MtLoggerBuilder::try_install_with_guard`] for a fallible version.
+    pub fn install_with_guard(self) -> MtLoggerGuard {
+        self.install();
+        MtLoggerGuard::new()
+    }
+
+    /// Installs the logger as the global instance, returning an [`MtLoggerGuard`] that flushes
+    /// it when dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error returned by [`MtLoggerBuilder::try_install`].
+    pub fn try_install_with_guard(self) -> Result<MtLoggerGuard, MtLoggerError> {
+        self.try_install()?;
+        Ok(MtLoggerGuard::new())
+    }
+
+    /// Spawns the logging thread and installs the logger as the global instance used by the
+    /// logging macros, shutting down the previously-installed instance (if any).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the logger cannot be built, or the previous instance cannot be shut down. See
+    /// [`MtLoggerBuilder::try_replace`] for a fallible version.
+    pub fn replace(self) {
+        self.try_replace()
+            .unwrap_or_else(|err| panic!("Failed to replace MtLogger: {}", err))
+    }
+
+    /// Spawns the logging thread and installs the logger as the global instance used by the
+    /// logging macros, shutting down the previously-installed instance (if any). Messages sent
+    /// to the previous instance by threads racing with the replacement may be dropped.
+    ///
+    /// The previous instance is freed right away if no other thread is using it, or otherwise by
+    /// a later replacement or shutdown.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error returned by [`MtLoggerBuilder::try_build`], in which case the previous
+    /// instance remains installed, or any error encountered while shutting it down.
+    pub fn try_replace(self) -> Result<(), MtLoggerError> {
+        match INSTANCE.replace(self.try_build()?) {
+            Some(old_logger) => old_logger.shutdown(),
+            None => Ok(()),
+        }
+    }
+}
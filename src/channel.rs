@@ -0,0 +1,713 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : channel.rs
+
+Copyright (C) 2021 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module defines the bounded Command channel between the Sender and
+    Receiver, which applies the configured OverflowPolicy to log messages
+    when the Receiver falls behind.
+
+    Each producing thread is given its own lock-free ring buffer, so threads
+    never contend with one another while logging. The Receiver drains all
+    ring buffers in the order their commands were sent, as measured by a
+    monotonic clock so that wall clock adjustments can't reorder them.
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use std::cell::{RefCell, UnsafeCell};
+use std::convert::TryFrom;
+use std::fmt;
+use std::mem::MaybeUninit;
+use std::sync::atomic::{self, AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{RecvError, RecvTimeoutError, SendError, TryRecvError};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
+
+use crate::{Command, OverflowPolicy};
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+/// Sending half of the channel
+pub struct LoggerTx {
+    shared: Arc<Shared>,
+    overflow_policy: OverflowPolicy,
+}
+
+/// Receiving half of the channel
+pub struct LoggerRx {
+    shared: Arc<Shared>,
+    rings: RefCell<RingSnapshot>,
+}
+
+struct Shared {
+    channel_id: usize,
+    created_at: Instant,
+    capacity: usize,
+    registry: Mutex<Vec<Arc<Ring>>>,
+    registry_generation: AtomicUsize,
+    fallback_ring: Mutex<Arc<Ring>>,
+    sender_count: AtomicUsize,
+    receiver_alive: AtomicBool,
+    receiver_sleeping: AtomicBool,
+    wake_lock: Mutex<()>,
+    wake_cvar: Condvar,
+    dropped_count: AtomicU64,
+}
+
+/// Bounded ring buffer with a single producer. Both the receiver and the producer itself (when
+/// dropping its oldest message) may consume from it, so each slot carries a sequence number
+/// indicating whether it is ready to be written or read at a given position.
+///
+/// A slot's sequence number is `2 * position` when it is free to be written at `position`, and
+/// `2 * position + 1` once a command has been published there.
+struct Ring {
+    slots: Box<[Slot]>,
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    producer_gone: AtomicBool,
+    producer_waiting: AtomicBool,
+    waiting_producer: Mutex<Option<Thread>>,
+}
+
+struct Slot {
+    sequence: AtomicUsize,
+    send_nanos: AtomicU64,
+    is_log_msg: AtomicBool,
+    cmd: UnsafeCell<MaybeUninit<Command>>,
+}
+
+/// The receiver's copy of the ring registry, refreshed whenever a ring is added or removed
+struct RingSnapshot {
+    generation: usize,
+    rings: Vec<Arc<Ring>>,
+}
+
+/// A thread's handle to its ring for a given channel
+struct LocalRing {
+    channel_id: usize,
+    shared: Arc<Shared>,
+    ring: Arc<Ring>,
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Static Variables
+///////////////////////////////////////////////////////////////////////////////
+
+/// Source of unique channel IDs, used to find a thread's ring for a given channel
+static NEXT_CHANNEL_ID: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static LOCAL_RINGS: RefCell<Vec<LocalRing>> = const { RefCell::new(Vec::new()) };
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Object Implementation
+///////////////////////////////////////////////////////////////////////////////
+
+/// Creates a channel holding at most `capacity` commands per producing thread
+pub fn channel(capacity: usize, overflow_policy: OverflowPolicy) -> (LoggerTx, LoggerRx) {
+    // A zero-capacity channel could never accept a message
+    let capacity = capacity.max(1);
+
+    let fallback_ring = Arc::new(Ring::new(capacity));
+    let shared = Arc::new(Shared {
+        channel_id: NEXT_CHANNEL_ID.fetch_add(1, Ordering::Relaxed),
+        created_at: Instant::now(),
+        capacity,
+        registry: Mutex::new(vec![Arc::clone(&fallback_ring)]),
+        registry_generation: AtomicUsize::new(0),
+        fallback_ring: Mutex::new(fallback_ring),
+        sender_count: AtomicUsize::new(1),
+        receiver_alive: AtomicBool::new(true),
+        receiver_sleeping: AtomicBool::new(false),
+        wake_lock: Mutex::new(()),
+        wake_cvar: Condvar::new(),
+        dropped_count: AtomicU64::new(0),
+    });
+
+    (
+        LoggerTx {
+            shared: Arc::clone(&shared),
+            overflow_policy,
+        },
+        LoggerRx {
+            shared,
+            rings: RefCell::new(RingSnapshot {
+                generation: usize::MAX,
+                rings: Vec::new(),
+            }),
+        },
+    )
+}
+
+impl LoggerTx {
+    /*  *  *  *  *  *  *  *\
+     *  Utility Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Queues a command, applying the overflow policy if the command is a log message.
+    ///
+    /// Other commands are never dropped, and always block until there is room in the channel.
+    pub fn send(&self, cmd: Command) -> Result<(), SendError<Command>> {
+        if !self.shared.receiver_alive.load(Ordering::SeqCst) {
+            return Err(SendError(cmd));
+        }
+
+        // Use this thread's ring, falling back to the shared ring if thread-locals are unavailable
+        // (i.e., while the thread is being torn down)
+        let mut cmd = Some(cmd);
+        let send_result = LOCAL_RINGS.try_with(|local_rings| {
+            let ring = self.local_ring(&mut local_rings.borrow_mut());
+            self.send_to_ring(&ring, cmd.take().unwrap())
+        });
+
+        match send_result {
+            Ok(send_result) => send_result,
+            Err(_) => {
+                let fallback_ring = self.shared.lock_fallback_ring();
+                self.send_to_ring(&fallback_ring, cmd.take().unwrap())
+            }
+        }
+    }
+
+
+    /*  *  *  *  *  *  *\
+     * Helper Methods *
+    \*  *  *  *  *  *  */
+
+    /// Finds this thread's ring for this channel, creating and registering one if necessary
+    fn local_ring(&self, local_rings: &mut Vec<LocalRing>) -> Arc<Ring> {
+        if let Some(local_ring) = local_rings
+            .iter()
+            .find(|local_ring| local_ring.channel_id == self.shared.channel_id)
+        {
+            return Arc::clone(&local_ring.ring);
+        }
+
+        // Forget rings belonging to channels whose receivers are gone, e.g., replaced loggers
+        local_rings.retain(|local_ring| local_ring.shared.receiver_alive.load(Ordering::Relaxed));
+
+        let ring = Arc::new(Ring::new(self.shared.capacity));
+        self.shared.lock_registry().push(Arc::clone(&ring));
+        self.shared
+            .registry_generation
+            .fetch_add(1, Ordering::Release);
+
+        local_rings.push(LocalRing {
+            channel_id: self.shared.channel_id,
+            shared: Arc::clone(&self.shared),
+            ring: Arc::clone(&ring),
+        });
+
+        ring
+    }
+
+    fn send_to_ring(&self, ring: &Ring, cmd: Command) -> Result<(), SendError<Command>> {
+        let overflow_policy = match &cmd {
+            Command::LogMsg(_) => self.overflow_policy,
+            _ => OverflowPolicy::Block,
+        };
+        let send_nanos = self.shared.nanos_since_created();
+
+        let start_time = Instant::now();
+        let mut cmd = cmd;
+        loop {
+            cmd = match ring.push(cmd, send_nanos) {
+                Ok(()) => break,
+                Err(cmd) => cmd,
+            };
+
+            // The ring is full, make sure the receiver is awake to drain it
+            self.shared.wake_receiver();
+
+            let timeout = match overflow_policy {
+                OverflowPolicy::Block => None,
+                OverflowPolicy::DropNewest => {
+                    self.shared.dropped_count.fetch_add(1, Ordering::Relaxed);
+                    return Ok(());
+                }
+                OverflowPolicy::DropOldest => {
+                    if ring.pop(true).is_some() {
+                        self.shared.dropped_count.fetch_add(1, Ordering::Relaxed);
+                        continue;
+                    }
+
+                    // Only log messages may be dropped, so if the oldest entry is another command,
+                    // drop the message being sent instead
+                    if ring.is_full() {
+                        self.shared.dropped_count.fetch_add(1, Ordering::Relaxed);
+                        return Ok(());
+                    }
+                    continue;
+                }
+                OverflowPolicy::BlockTimeout(timeout) => {
+                    let elapsed = start_time.elapsed();
+                    if elapsed >= timeout {
+                        self.shared.dropped_count.fetch_add(1, Ordering::Relaxed);
+                        return Ok(());
+                    }
+                    Some(timeout - elapsed)
+                }
+            };
+
+            if !self.shared.receiver_alive.load(Ordering::SeqCst) {
+                return Err(SendError(cmd));
+            }
+
+            // Sleep until the receiver makes room
+            ring.wait_for_room(timeout);
+        }
+
+        // Make the new command visible before checking on the receiver
+        atomic::fence(Ordering::SeqCst);
+
+        // If the receiver died while the command was being queued, it will never be processed.
+        // Discard everything in the ring, the last entry being the command just queued.
+        if !self.shared.receiver_alive.load(Ordering::SeqCst) {
+            let mut last_cmd = None;
+            while let Some(cmd) = ring.pop(false) {
+                last_cmd = Some(cmd);
+            }
+            return match last_cmd {
+                Some(cmd) => Err(SendError(cmd)),
+                None => Ok(()),
+            };
+        }
+
+        self.shared.wake_receiver();
+
+        Ok(())
+    }
+}
+
+impl LoggerRx {
+    /*  *  *  *  *  *  *  *\
+     *  Utility Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Blocks until a command is available, or every sender has hung up
+    pub fn recv(&self) -> Result<Command, RecvError> {
+        loop {
+            match self.recv_timeout(Duration::from_secs(1)) {
+                Ok(cmd) => return Ok(cmd),
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => return Err(RecvError),
+            }
+        }
+    }
+
+    /// Blocks until a command is available, every sender has hung up, or the timeout elapses
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<Command, RecvTimeoutError> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            match self.try_recv() {
+                Ok(cmd) => return Ok(cmd),
+                Err(TryRecvError::Disconnected) => return Err(RecvTimeoutError::Disconnected),
+                Err(TryRecvError::Empty) => (),
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(RecvTimeoutError::Timeout);
+            }
+
+            // Announce that we're going to sleep, then check once more so that a command sent in
+            // the meantime can't be missed. Senders must take the lock to wake us, so they can't
+            // slip in between the check and the wait.
+            let wake_guard = self.shared.lock_wake();
+            self.shared.receiver_sleeping.store(true, Ordering::SeqCst);
+            atomic::fence(Ordering::SeqCst);
+            if self.is_empty() && !self.is_disconnected() {
+                let _ = self
+                    .shared
+                    .wake_cvar
+                    .wait_timeout(wake_guard, deadline - now)
+                    .unwrap_or_else(PoisonError::into_inner);
+            }
+            self.shared.receiver_sleeping.store(false, Ordering::SeqCst);
+        }
+    }
+
+    /// Returns a command if one is immediately available
+    pub fn try_recv(&self) -> Result<Command, TryRecvError> {
+        // Check for senders before draining, so nothing sent by the last sender can be missed
+        let disconnected = self.is_disconnected();
+
+        match self.pop_earliest() {
+            Some(cmd) => Ok(cmd),
+            None if disconnected => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    /// Returns the number of log messages dropped since the last call, and resets the count
+    pub fn take_dropped_count(&self) -> u64 {
+        self.shared.dropped_count.swap(0, Ordering::Relaxed)
+    }
+
+
+    /*  *  *  *  *  *  *\
+     * Helper Methods *
+    \*  *  *  *  *  *  */
+
+    /// Pops the earliest-sent command from the heads of all rings
+    fn pop_earliest(&self) -> Option<Command> {
+        self.refresh_rings();
+        let rings = self.rings.borrow();
+
+        loop {
+            let (mut ring_idx, is_log_msg) = Self::find_earliest(&rings.rings)?;
+
+            // Anything sent before a command on another thread must be processed before it, so
+            // look again now that the command is visible to catch any such messages
+            if !is_log_msg {
+                ring_idx = Self::find_earliest(&rings.rings)?.0;
+            }
+
+            // The pop can only fail if the producer dropped its oldest message in the meantime
+            if let Some(cmd) = rings.rings[ring_idx].pop(false) {
+                rings.rings[ring_idx].wake_producer();
+                return Some(cmd);
+            }
+        }
+    }
+
+    /// Returns the index of the ring whose head was sent earliest, and whether that head is a
+    /// log message
+    fn find_earliest(rings: &[Arc<Ring>]) -> Option<(usize, bool)> {
+        rings
+            .iter()
+            .enumerate()
+            .filter_map(|(ring_idx, ring)| {
+                ring.peek()
+                    .map(|(send_nanos, is_log_msg)| (send_nanos, ring_idx, is_log_msg))
+            })
+            .min_by_key(|&(send_nanos, ring_idx, _)| (send_nanos, ring_idx))
+            .map(|(_, ring_idx, is_log_msg)| (ring_idx, is_log_msg))
+    }
+
+    /// Updates the local ring snapshot if rings have been added or removed
+    fn refresh_rings(&self) {
+        let generation = self.shared.registry_generation.load(Ordering::Acquire);
+        let mut rings = self.rings.borrow_mut();
+        if rings.generation == generation {
+            return;
+        }
+
+        // Forget rings whose producer threads have exited, once they have been drained
+        let mut registry = self.shared.lock_registry();
+        registry.retain(|ring| !ring.producer_gone.load(Ordering::Acquire) || !ring.is_empty());
+
+        rings.rings = registry.clone();
+        rings.generation = generation;
+    }
+
+    fn is_empty(&self) -> bool {
+        self.refresh_rings();
+        self.rings.borrow().rings.iter().all(|ring| ring.is_empty())
+    }
+
+    fn is_disconnected(&self) -> bool {
+        self.shared.sender_count.load(Ordering::SeqCst) == 0
+    }
+}
+
+impl Shared {
+    /*  *  *  *  *  *  *\
+     * Helper Methods *
+    \*  *  *  *  *  *  */
+
+    /// Returns the time elapsed since the channel was created, in nanoseconds. Unlike message
+    /// timestamps, this never goes backwards, so it orders commands sent by different threads.
+    fn nanos_since_created(&self) -> u64 {
+        u64::try_from(self.created_at.elapsed().as_nanos()).unwrap_or(u64::MAX)
+    }
+
+    /// Wakes the receiver if it is waiting for commands
+    fn wake_receiver(&self) {
+        if self.receiver_sleeping.load(Ordering::SeqCst) {
+            let _wake_guard = self.lock_wake();
+            self.wake_cvar.notify_one();
+        }
+    }
+
+    // Nothing panics while holding these locks, so their contents are always consistent
+
+    fn lock_registry(&self) -> MutexGuard<'_, Vec<Arc<Ring>>> {
+        self.registry.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn lock_fallback_ring(&self) -> MutexGuard<'_, Arc<Ring>> {
+        self.fallback_ring
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn lock_wake(&self) -> MutexGuard<'_, ()> {
+        self.wake_lock
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Ring {
+    /// Fully-qualified constructor
+    fn new(capacity: usize) -> Self {
+        let slots = (0..capacity)
+            .map(|slot_idx| Slot {
+                sequence: AtomicUsize::new(free_sequence(slot_idx)),
+                send_nanos: AtomicU64::new(0),
+                is_log_msg: AtomicBool::new(false),
+                cmd: UnsafeCell::new(MaybeUninit::uninit()),
+            })
+            .collect();
+
+        Self {
+            slots,
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            producer_gone: AtomicBool::new(false),
+            producer_waiting: AtomicBool::new(false),
+            waiting_producer: Mutex::new(None),
+        }
+    }
+
+
+    /*  *  *  *  *  *  *\
+     * Utility Methods *
+    \*  *  *  *  *  *  */
+
+    /// Appends a command, returning it back if the ring is full.
+    ///
+    /// Must only be called by the ring's producer.
+    fn push(&self, cmd: Command, send_nanos: u64) -> Result<(), Command> {
+        let position = self.tail.load(Ordering::Relaxed);
+        let slot = &self.slots[position % self.slots.len()];
+
+        // The slot is free once its previous occupant has been consumed
+        if slot.sequence.load(Ordering::Acquire) != free_sequence(position) {
+            return Err(cmd);
+        }
+
+        slot.send_nanos.store(send_nanos, Ordering::Relaxed);
+        slot.is_log_msg
+            .store(matches!(cmd, Command::LogMsg(_)), Ordering::Relaxed);
+        // SAFETY: The sequence number shows that no consumer can access this slot until it is
+        //         published below, and only the producer writes to the ring
+        unsafe { (*slot.cmd.get()).write(cmd) };
+        slot.sequence
+            .store(published_sequence(position), Ordering::Release);
+        self.tail.store(position + 1, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    /// Removes the oldest command, optionally only if it is a log message
+    fn pop(&self, log_msg_only: bool) -> Option<Command> {
+        let mut position = self.head.load(Ordering::Relaxed);
+
+        loop {
+            let slot = &self.slots[position % self.slots.len()];
+            let sequence = slot.sequence.load(Ordering::Acquire);
+
+            if sequence == published_sequence(position) {
+                if log_msg_only && !slot.is_log_msg.load(Ordering::Relaxed) {
+                    return None;
+                }
+
+                // Claim the slot, or try again if another consumer beat us to it
+                match self.head.compare_exchange_weak(
+                    position,
+                    position + 1,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        // SAFETY: The slot was published by the producer, and claiming it makes
+                        //         this the only consumer that can read it
+                        let cmd = unsafe { (*slot.cmd.get()).assume_init_read() };
+                        slot.sequence.store(
+                            free_sequence(position + self.slots.len()),
+                            Ordering::Release,
+                        );
+                        return Some(cmd);
+                    }
+                    Err(current_position) => position = current_position,
+                }
+            } else if sequence < published_sequence(position) {
+                // The slot has not been published yet, so the ring is empty
+                return None;
+            } else {
+                // Another consumer claimed this slot, catch up
+                position = self.head.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Returns the send time of the oldest command, and whether it is a log message
+    fn peek(&self) -> Option<(u64, bool)> {
+        let position = self.head.load(Ordering::Acquire);
+        let slot = &self.slots[position % self.slots.len()];
+
+        if slot.sequence.load(Ordering::Acquire) == published_sequence(position) {
+            Some((
+                slot.send_nanos.load(Ordering::Relaxed),
+                slot.is_log_msg.load(Ordering::Relaxed),
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Parks the calling producer until a consumer makes room in the ring, or the timeout
+    /// elapses. May return spuriously, so the caller must check for room again.
+    fn wait_for_room(&self, timeout: Option<Duration>) {
+        *self.lock_waiting_producer() = Some(thread::current());
+        self.producer_waiting.store(true, Ordering::SeqCst);
+
+        // Check once more now that consumers can see we're waiting, so that room made in the
+        // meantime can't be missed. An unpark that arrives before the park isn't lost either.
+        atomic::fence(Ordering::SeqCst);
+        if self.is_full() {
+            match timeout {
+                Some(timeout) => thread::park_timeout(timeout),
+                None => thread::park(),
+            }
+        }
+
+        self.producer_waiting.store(false, Ordering::SeqCst);
+    }
+
+    /// Unparks the producer if it is waiting for room in the ring
+    fn wake_producer(&self) {
+        // Make the freed slot visible before checking on the producer
+        atomic::fence(Ordering::SeqCst);
+        if self.producer_waiting.load(Ordering::SeqCst) {
+            if let Some(producer) = self.lock_waiting_producer().as_ref() {
+                producer.unpark();
+            }
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.peek().is_none()
+    }
+
+    fn is_full(&self) -> bool {
+        let position = self.tail.load(Ordering::Relaxed);
+        self.slots[position % self.slots.len()]
+            .sequence
+            .load(Ordering::Acquire)
+            != free_sequence(position)
+    }
+
+    // Nothing panics while holding this lock, so its contents are always consistent
+    fn lock_waiting_producer(&self) -> MutexGuard<'_, Option<Thread>> {
+        self.waiting_producer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Static Functions
+///////////////////////////////////////////////////////////////////////////////
+
+/// Sequence number of a slot that is free to be written at the given position
+fn free_sequence(position: usize) -> usize {
+    position.wrapping_mul(2)
+}
+
+/// Sequence number of a slot whose command at the given position is ready to be read
+fn published_sequence(position: usize) -> usize {
+    position.wrapping_mul(2) + 1
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Trait Implementations
+///////////////////////////////////////////////////////////////////////////////
+
+// SAFETY: Access to each slot's command is synchronized by the slot's sequence number
+unsafe impl Sync for Ring {}
+
+impl Drop for Ring {
+    fn drop(&mut self) {
+        // Drop any commands that were never consumed
+        while self.pop(false).is_some() {}
+    }
+}
+
+impl Drop for LocalRing {
+    fn drop(&mut self) {
+        // Let the receiver know it can forget this ring once it has been drained
+        self.ring.producer_gone.store(true, Ordering::Release);
+        self.shared
+            .registry_generation
+            .fetch_add(1, Ordering::Release);
+    }
+}
+
+impl Clone for LoggerTx {
+    fn clone(&self) -> Self {
+        self.shared.sender_count.fetch_add(1, Ordering::SeqCst);
+
+        Self {
+            shared: Arc::clone(&self.shared),
+            overflow_policy: self.overflow_policy,
+        }
+    }
+}
+
+impl Drop for LoggerTx {
+    fn drop(&mut self) {
+        // Wake the receiver so it can notice that every sender has hung up
+        if self.shared.sender_count.fetch_sub(1, Ordering::SeqCst) == 1 {
+            let _wake_guard = self.shared.lock_wake();
+            self.shared.wake_cvar.notify_one();
+        }
+    }
+}
+
+impl Drop for LoggerRx {
+    fn drop(&mut self) {
+        // Stop accepting commands, then discard anything left so that senders waiting on an
+        // acknowledgement or for room in their ring are released
+        self.shared.receiver_alive.store(false, Ordering::SeqCst);
+        atomic::fence(Ordering::SeqCst);
+
+        for ring in self.shared.lock_registry().iter() {
+            while ring.pop(false).is_some() {}
+            ring.wake_producer();
+        }
+    }
+}
+
+impl fmt::Debug for LoggerTx {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LoggerTx")
+            .field("capacity", &self.shared.capacity)
+            .field("overflow_policy", &self.overflow_policy)
+            .finish()
+    }
+}
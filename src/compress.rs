@@ -0,0 +1,159 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : compress.rs
+
+Copyright (C) 2021 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module defines the Compressor, which gzips rotated logfiles in its
+    own thread so that compression never stalls the logging thread.
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+
+use chrono::Local;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::logfile::GZIP_EXTENSION;
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Named Constants
+///////////////////////////////////////////////////////////////////////////////
+
+/// Extension appended to the names of compressed logfiles while they are being written
+const PARTIAL_EXTENSION: &str = ".tmp";
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+/// Work performed by the compression thread
+pub type CompressionJob = Box<dyn FnOnce() -> io::Result<()> + Send>;
+
+/// Handle to the compression thread, which runs jobs in the order they were submitted
+#[derive(Debug)]
+pub struct Compressor {
+    job_tx: Option<mpsc::Sender<(PathBuf, CompressionJob)>>,
+    worker_handle: Option<JoinHandle<()>>,
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Object Implementation
+///////////////////////////////////////////////////////////////////////////////
+
+impl Compressor {
+    /// Spawns the compression thread, which reports failed jobs on stderr
+    pub fn new(thread_name: String, timestamp_format: String) -> io::Result<Self> {
+        let (job_tx, job_rx) = mpsc::channel::<(PathBuf, CompressionJob)>();
+        let worker_handle = thread::Builder::new().name(thread_name).spawn(move || {
+            for (path, job) in job_rx {
+                if let Err(err) = job() {
+                    eprintln!(
+                        "{}: Encountered error '{}' while attempting to compress rotated logfile '{}'.",
+                        Local::now().format(&timestamp_format),
+                        err,
+                        path.display()
+                    );
+                }
+            }
+        })?;
+
+        Ok(Self {
+            job_tx: Some(job_tx),
+            worker_handle: Some(worker_handle),
+        })
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Utility Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Queues a job compressing the logfile at `path`
+    pub fn submit(&self, path: PathBuf, job: CompressionJob) {
+        if let Some(job_tx) = &self.job_tx {
+            // Only fails if the compression thread panicked, in which case there is no one to
+            // report the failure to
+            let _ = job_tx.send((path, job));
+        }
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Static Functions
+///////////////////////////////////////////////////////////////////////////////
+
+/// Returns the path of the compressed logfile for the logfile at `path`, e.g., `app.log.1.gz`
+pub fn gzip_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().map_or_else(OsString::new, OsString::from);
+    file_name.push(GZIP_EXTENSION);
+
+    path.with_file_name(file_name)
+}
+
+/// Compresses the file at `src` into `dest`, then deletes `src`.
+///
+/// The compressed file is written under a temporary name first, so that a partially-written file
+/// is never mistaken for a complete one. If `src` no longer exists, e.g., because it was deleted
+/// by the retention policy in the meantime, there is nothing to do.
+pub fn gzip_file(src: &Path, dest: &Path) -> io::Result<()> {
+    let mut reader = match File::open(src) {
+        Ok(src_file) => BufReader::new(src_file),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+
+    let mut partial_name = dest.file_name().map_or_else(OsString::new, OsString::from);
+    partial_name.push(PARTIAL_EXTENSION);
+    let partial_path = dest.with_file_name(partial_name);
+
+    let mut encoder = GzEncoder::new(
+        BufWriter::new(File::create(&partial_path)?),
+        Compression::default(),
+    );
+    io::copy(&mut reader, &mut encoder)?;
+    encoder.finish()?.into_inner()?.sync_all()?;
+
+    fs::rename(&partial_path, dest)?;
+    match fs::remove_file(src) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Trait Implementations
+///////////////////////////////////////////////////////////////////////////////
+
+impl Drop for Compressor {
+    fn drop(&mut self) {
+        // Closing the channel stops the compression thread once every queued job is done
+        self.job_tx = None;
+        if let Some(worker_handle) = self.worker_handle.take() {
+            let _ = worker_handle.join();
+        }
+    }
+}
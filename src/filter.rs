@@ -0,0 +1,158 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : filter.rs
+
+Copyright (C) 2021 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module defines the Target Filter, which maps message targets to the
+    set of Levels recorded for them. The logging thread applies it to each
+    message, while the sending side keeps a mirror to determine which messages
+    could possibly be recorded. It also parses filter directive strings, e.g.,
+    `info,my_app::net=trace`, into levels for the Target Filter.
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use crate::{Level, LevelMask, MtLoggerError};
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+/// Table of target prefixes and their level masks, falling back to a default mask
+#[derive(Clone, Debug)]
+pub struct TargetFilter {
+    default_mask: LevelMask,
+    target_masks: Vec<(String, LevelMask)>,
+}
+
+/// Levels parsed from a filter directive string
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Directives {
+    pub default_level: Option<Level>,
+    pub target_levels: Vec<(String, Level)>,
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Object Implementation
+///////////////////////////////////////////////////////////////////////////////
+
+impl TargetFilter {
+    /// Fully-qualified constructor
+    pub fn new(default_mask: LevelMask, target_masks: &[(String, LevelMask)]) -> Self {
+        let mut target_filter = Self {
+            default_mask,
+            target_masks: Vec::new(),
+        };
+        for (target, mask) in target_masks {
+            target_filter.set_target_mask(target, *mask);
+        }
+
+        target_filter
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Accessor Methods  *
+    \*  *  *  *  *  *  *  */
+
+    /// Returns the levels recorded for a message with the given target and function name.
+    ///
+    /// The most specific matching prefix wins, e.g., `net::tcp` over `net` for `net::tcp::conn`.
+    pub fn mask_for(&self, target: &str, fn_name: &str) -> LevelMask {
+        self.target_masks
+            .iter()
+            .filter(|(prefix, _)| target_matches(target, prefix) || target_matches(fn_name, prefix))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.default_mask, |(_, mask)| *mask)
+    }
+
+    /// Returns every level recorded for any target
+    pub fn union_mask(&self) -> LevelMask {
+        self.target_masks
+            .iter()
+            .fold(self.default_mask, |union_mask, (_, mask)| {
+                union_mask | *mask
+            })
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Mutator Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Sets the mask used for targets without a more specific entry
+    pub fn set_default_mask(&mut self, mask: LevelMask) {
+        self.default_mask = mask;
+    }
+
+    /// Sets the mask for the given target prefix, replacing any existing entry
+    pub fn set_target_mask(&mut self, target: &str, mask: LevelMask) {
+        match self
+            .target_masks
+            .iter_mut()
+            .find(|(prefix, _)| prefix == target)
+        {
+            Some(entry) => entry.1 = mask,
+            None => self.target_masks.push((target.to_string(), mask)),
+        }
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Static Functions
+///////////////////////////////////////////////////////////////////////////////
+
+/// Parses a comma-separated list of `level` and `target=level` directives.
+///
+/// Whitespace around directives is ignored, as are empty directives. If multiple default levels
+/// are given, the last one wins.
+pub fn parse_directives(directives: &str) -> Result<Directives, MtLoggerError> {
+    let mut parsed = Directives::default();
+
+    for directive in directives.split(',').map(str::trim) {
+        if directive.is_empty() {
+            continue;
+        }
+
+        let invalid = || MtLoggerError::InvalidDirective(directive.to_string());
+        let mut parts = directive.splitn(2, '=');
+        match (parts.next(), parts.next()) {
+            (Some(level), None) => {
+                parsed.default_level = Some(level.parse().map_err(|_| invalid())?);
+            }
+            (Some(target), Some(level)) => {
+                let target = target.trim();
+                if target.is_empty() || target.contains(char::is_whitespace) {
+                    return Err(invalid());
+                }
+                let level = level.trim().parse().map_err(|_| invalid())?;
+                parsed.target_levels.push((target.to_string(), level));
+            }
+            _ => return Err(invalid()),
+        }
+    }
+
+    Ok(parsed)
+}
+
+/// Checks whether the prefix matches the target on a `::` path segment boundary
+fn target_matches(target: &str, prefix: &str) -> bool {
+    match target.strip_prefix(prefix) {
+        Some(remainder) => remainder.is_empty() || remainder.starts_with("::"),
+        None => false,
+    }
+}
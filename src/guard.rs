@@ -0,0 +1,91 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : guard.rs
+
+Copyright (C) 2021 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module defines the Logger Guard, which flushes or shuts down the
+    global instance when it goes out of scope so that messages logged just
+    before a program exits are not lost.
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use crate::INSTANCE;
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+/// Flushes the global instance when dropped, guaranteeing that every message sent beforehand has
+/// been recorded.
+///
+/// Returned by [`MtLoggerBuilder::install_with_guard`] and [`mt_new_guard!`]. Bind it to a named
+/// variable such as `_guard` at the top of `main`, as binding it to `_` drops it immediately.
+///
+/// The guard acts on whichever instance is installed when it is dropped, so it remains effective
+/// after the global instance is replaced. Errors are ignored, as they cannot be returned from
+/// `drop()`; call [`mt_flush!`] or [`mt_shutdown!`] directly to handle them.
+///
+/// [`MtLoggerBuilder::install_with_guard`]: crate::MtLoggerBuilder::install_with_guard
+#[must_use = "the global instance is flushed as soon as the guard is dropped"]
+#[derive(Debug)]
+pub struct MtLoggerGuard {
+    shutdown_on_drop: bool,
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Object Implementation
+///////////////////////////////////////////////////////////////////////////////
+
+impl MtLoggerGuard {
+    /// Creates a guard that flushes the global instance when dropped
+    pub(crate) fn new() -> Self {
+        Self {
+            shutdown_on_drop: false,
+        }
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Builder Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Shuts down the global instance when the guard is dropped, rather than flushing it.
+    ///
+    /// This also closes the logfile and stops the logging thread, so any messages sent afterwards
+    /// are discarded.
+    pub fn shutdown_on_drop(mut self) -> Self {
+        self.shutdown_on_drop = true;
+        self
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Trait Implementations
+///////////////////////////////////////////////////////////////////////////////
+
+impl Drop for MtLoggerGuard {
+    fn drop(&mut self) {
+        if let Some(instance) = INSTANCE.get() {
+            let _ = if self.shutdown_on_drop {
+                instance.shutdown()
+            } else {
+                instance.flush()
+            };
+        }
+    }
+}
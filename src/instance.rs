@@ -0,0 +1,379 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : instance.rs
+
+Copyright (C) 2021 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module defines the Global Instance cell, which holds the logger used
+    by the macros and allows it to be replaced at runtime without requiring
+    a lock to read it. Replaced instances are reclaimed once no thread can
+    still be reading them, using epochs pinned by each reading thread.
+
+    Reading never blocks: readers only pin and unpin their epoch, and find
+    their slot in a lock-free registry. Reclamation is left to replacement
+    and shutdown.
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use std::cell::Cell;
+use std::fmt;
+use std::iter;
+use std::ops::Deref;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use crate::MtLogger;
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+/// Atomically-swappable cell containing the global logger instance.
+///
+/// Readers pin the current epoch while they hold a reference to the instance. Replaced instances
+/// are freed by the first replacement or shutdown after every reader that could still be holding
+/// a reference to them has unpinned.
+pub struct GlobalInstance {
+    logger_ptr: AtomicPtr<MtLogger>,
+}
+
+/// Reference to the installed instance, which is kept alive until the reference is dropped
+pub struct InstanceRef<'a> {
+    logger: &'a MtLogger,
+    _pin: EpochPin,
+}
+
+/// Instance removed from the cell by [`GlobalInstance::replace`], which is freed once it is
+/// dropped and no reader can still be using it
+pub struct ReplacedInstance {
+    logger_ptr: *mut MtLogger,
+}
+
+/// Keeps replaced instances from being freed while it exists
+struct EpochPin {
+    registered: bool,
+}
+
+/// Epoch pinned by a thread while it holds a reference to an instance, or zero if none.
+///
+/// Slots form a lock-free list that only ever grows. They are never freed, and are reused by
+/// later threads once their owner exits, so the list is only as long as the greatest number of
+/// threads that have read an instance at once.
+struct ReaderSlot {
+    pinned_epoch: AtomicU64,
+    in_use: AtomicBool,
+    next: AtomicPtr<ReaderSlot>,
+}
+
+/// A thread's registration in the reader registry
+struct LocalReader {
+    slot: &'static ReaderSlot,
+    pin_depth: Cell<usize>,
+}
+
+/// Replaced instance awaiting reclamation, along with the epoch it was retired in
+struct Retired {
+    epoch: u64,
+    logger_ptr: *mut MtLogger,
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Static Variables
+///////////////////////////////////////////////////////////////////////////////
+
+/// Current epoch, advanced whenever an instance is retired. Zero is never a valid epoch.
+static EPOCH: AtomicU64 = AtomicU64::new(1);
+
+/// Head of the list of reader slots
+static READERS: AtomicPtr<ReaderSlot> = AtomicPtr::new(ptr::null_mut());
+
+/// Number of readers that could not use a thread-local slot, e.g., during thread teardown
+static UNREGISTERED_READERS: AtomicUsize = AtomicUsize::new(0);
+
+/// Replaced instances that may still be in use
+static RETIRED: Mutex<Vec<Retired>> = Mutex::new(Vec::new());
+
+thread_local! {
+    static LOCAL_READER: LocalReader = LocalReader::register();
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Object Implementation
+///////////////////////////////////////////////////////////////////////////////
+
+impl GlobalInstance {
+    /// Creates an empty cell
+    pub const fn new() -> Self {
+        Self {
+            logger_ptr: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Accessor Methods  *
+    \*  *  *  *  *  *  *  */
+
+    /// Returns the current instance, or `None` if one has not been installed
+    pub fn get(&self) -> Option<InstanceRef<'_>> {
+        // Pin before loading, so that a concurrent replacement can't free what we load
+        let pin = EpochPin::new();
+        let logger_ptr = self.logger_ptr.load(Ordering::SeqCst);
+
+        // SAFETY: Non-null pointers always come from Box::into_raw(), and are only freed once
+        // every reader pinned before they were replaced has unpinned
+        unsafe { logger_ptr.as_ref() }.map(|logger| InstanceRef { logger, _pin: pin })
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Mutator Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Installs the given instance, returning it back if an instance was already installed
+    pub fn set(&self, logger: MtLogger) -> Result<(), MtLogger> {
+        let new_ptr = Box::into_raw(Box::new(logger));
+
+        match self.logger_ptr.compare_exchange(
+            ptr::null_mut(),
+            new_ptr,
+            Ordering::SeqCst,
+            Ordering::SeqCst,
+        ) {
+            Ok(_) => Ok(()),
+            // SAFETY: The pointer was never published, so this is the only reference to it
+            Err(_) => Err(*unsafe { Box::from_raw(new_ptr) }),
+        }
+    }
+
+    /// Installs the given instance, returning the previously-installed instance (if any)
+    pub fn replace(&self, logger: MtLogger) -> Option<ReplacedInstance> {
+        let new_ptr = Box::into_raw(Box::new(logger));
+        let old_ptr = self.logger_ptr.swap(new_ptr, Ordering::SeqCst);
+
+        if old_ptr.is_null() {
+            None
+        } else {
+            Some(ReplacedInstance {
+                logger_ptr: old_ptr,
+            })
+        }
+    }
+}
+
+impl EpochPin {
+    /// Pins the current epoch on this thread, unless it is already pinned
+    fn new() -> Self {
+        let registered = LOCAL_READER
+            .try_with(|local_reader| {
+                let pin_depth = local_reader.pin_depth.get();
+                if pin_depth == 0 {
+                    local_reader
+                        .slot
+                        .pinned_epoch
+                        .store(EPOCH.load(Ordering::SeqCst), Ordering::SeqCst);
+                }
+                local_reader.pin_depth.set(pin_depth + 1);
+            })
+            .is_ok();
+
+        // Without a slot, hold off all reclamation instead
+        if !registered {
+            UNREGISTERED_READERS.fetch_add(1, Ordering::SeqCst);
+        }
+
+        Self { registered }
+    }
+}
+
+impl LocalReader {
+    /// Claims a slot in the reader registry for this thread, reusing one left by an exited
+    /// thread if possible
+    fn register() -> Self {
+        let slot = reader_slots()
+            .find(|slot| {
+                slot.in_use
+                    .compare_exchange(false, true, Ordering::SeqCst, Ordering::Relaxed)
+                    .is_ok()
+            })
+            .unwrap_or_else(|| {
+                let slot = Box::leak(Box::new(ReaderSlot {
+                    pinned_epoch: AtomicU64::new(0),
+                    in_use: AtomicBool::new(true),
+                    next: AtomicPtr::new(ptr::null_mut()),
+                }));
+
+                // Push the new slot onto the front of the list
+                let mut head = READERS.load(Ordering::SeqCst);
+                loop {
+                    slot.next.store(head, Ordering::Relaxed);
+                    match READERS.compare_exchange_weak(
+                        head,
+                        slot,
+                        Ordering::SeqCst,
+                        Ordering::SeqCst,
+                    ) {
+                        Ok(_) => break,
+                        Err(current_head) => head = current_head,
+                    }
+                }
+
+                slot
+            });
+
+        Self {
+            slot,
+            pin_depth: Cell::new(0),
+        }
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Static Functions
+///////////////////////////////////////////////////////////////////////////////
+
+/// Frees every replaced instance that no reader can still be using.
+///
+/// Readers never reclaim, so this is called whenever an instance is replaced or shut down.
+pub(crate) fn reclaim() {
+    let mut retired = lock_ignoring_poison(&RETIRED);
+    if retired.is_empty() || UNREGISTERED_READERS.load(Ordering::SeqCst) > 0 {
+        return;
+    }
+
+    // Readers that pinned a later epoch than an instance's started after it was replaced
+    let oldest_pinned_epoch = reader_slots()
+        .map(|slot| slot.pinned_epoch.load(Ordering::SeqCst))
+        .filter(|pinned_epoch| *pinned_epoch != 0)
+        .min()
+        .unwrap_or(u64::MAX);
+    let (freeable, still_retired): (Vec<_>, Vec<_>) = retired
+        .drain(..)
+        .partition(|retired_logger| oldest_pinned_epoch > retired_logger.epoch);
+    *retired = still_retired;
+    drop(retired);
+
+    for retired_logger in freeable {
+        // SAFETY: The pointer came from Box::into_raw(), was removed from the cell before its
+        // epoch ended, and every reader that pinned an epoch up to then has since unpinned
+        drop(unsafe { Box::from_raw(retired_logger.logger_ptr) });
+    }
+}
+
+/// Queues the given instance to be freed once no reader can still be using it
+fn retire(logger_ptr: *mut MtLogger) {
+    let epoch = EPOCH.fetch_add(1, Ordering::SeqCst);
+    lock_ignoring_poison(&RETIRED).push(Retired { epoch, logger_ptr });
+
+    reclaim();
+}
+
+/// Iterates over every slot in the reader registry
+fn reader_slots() -> impl Iterator<Item = &'static ReaderSlot> {
+    // SAFETY: Slots are leaked when they are added to the list, so they are never freed
+    iter::successors(
+        unsafe { READERS.load(Ordering::SeqCst).as_ref() },
+        |slot| unsafe { slot.next.load(Ordering::SeqCst).as_ref() },
+    )
+}
+
+/// Locks the mutex, ignoring poisoning as the protected data is always left consistent
+fn lock_ignoring_poison<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Trait Implementations
+///////////////////////////////////////////////////////////////////////////////
+
+impl Default for GlobalInstance {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for GlobalInstance {
+    fn drop(&mut self) {
+        let logger_ptr = *self.logger_ptr.get_mut();
+        if !logger_ptr.is_null() {
+            retire(logger_ptr);
+        }
+    }
+}
+
+impl Deref for InstanceRef<'_> {
+    type Target = MtLogger;
+
+    fn deref(&self) -> &MtLogger {
+        self.logger
+    }
+}
+
+impl fmt::Debug for InstanceRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.logger, f)
+    }
+}
+
+impl Deref for ReplacedInstance {
+    type Target = MtLogger;
+
+    fn deref(&self) -> &MtLogger {
+        // SAFETY: The pointer is only retired once this handle is dropped
+        unsafe { &*self.logger_ptr }
+    }
+}
+
+impl Drop for ReplacedInstance {
+    fn drop(&mut self) {
+        retire(self.logger_ptr);
+    }
+}
+
+impl Drop for EpochPin {
+    fn drop(&mut self) {
+        if self.registered {
+            let _ = LOCAL_READER.try_with(|local_reader| {
+                let pin_depth = local_reader.pin_depth.get() - 1;
+                local_reader.pin_depth.set(pin_depth);
+                if pin_depth == 0 {
+                    local_reader.slot.pinned_epoch.store(0, Ordering::SeqCst);
+                }
+            });
+        } else {
+            UNREGISTERED_READERS.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+impl Drop for LocalReader {
+    fn drop(&mut self) {
+        // Hand the slot over to the next thread that registers
+        self.slot.pinned_epoch.store(0, Ordering::SeqCst);
+        self.slot.in_use.store(false, Ordering::SeqCst);
+    }
+}
+
+// SAFETY: Retired instances are only accessed through the RETIRED mutex, and MtLogger is Send
+unsafe impl Send for Retired {}
+
+// SAFETY: The handle only hands out shared references, and MtLogger is Sync
+unsafe impl Send for ReplacedInstance {}
+unsafe impl Sync for ReplacedInstance {}
@@ -27,28 +27,50 @@ Purpose:
 
 use std::error::Error;
 use std::fmt;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::io;
+use std::ops::{BitOr, BitOrAssign};
+use std::panic::Location;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
 use std::sync::mpsc::{self, RecvError, SendError};
-use std::sync::Arc;
-use std::thread;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle, ThreadId};
+use std::time::Duration;
 
 use chrono::DateTime;
 use chrono::Local;
-use once_cell::sync::OnceCell;
 
 
 ///////////////////////////////////////////////////////////////////////////////
 //  Named Constants
 ///////////////////////////////////////////////////////////////////////////////
 
-// Buffer size of the sync_channel for sending log messages
-const CHANNEL_SIZE: usize = 512;
+/// Threshold above every [`Level`], used when all messages are stripped
+const LEVEL_OFF: u8 = 0x40;
+
+/// Bits of every [`Level`] combined
+const ALL_LEVELS: u8 = LEVEL_OFF - 1;
+
+/// Environment variable read by [`mt_new!`] and the other initialization macros for filter
+/// directives, see [`MtLoggerBuilder::directives`].
+pub const DIRECTIVES_ENV_VAR: &str = "MT_LOG";
+
+/// Minimum [`Level`] written to StdErr rather than StdOut by [`OutputStream::Split`]
+const SPLIT_STDERR_LEVEL: Level = Level::Warning;
+
+#[doc(hidden)]
+/// Most verbose [`Level`] compiled into [`mt_log!`], as selected by the `max_level_*` and
+/// `release_max_level_*` cargo features. Calls below this level are optimized away entirely.
+pub const STATIC_MAX_LEVEL: u8 = static_max_level();
 
 
 ///////////////////////////////////////////////////////////////////////////////
 //  Module Declarations
 ///////////////////////////////////////////////////////////////////////////////
 
+#[doc(hidden)]
+pub mod channel;
+
 #[doc(hidden)]
 pub mod sender;
 use self::sender::Sender;
@@ -57,6 +79,30 @@ use self::sender::Sender;
 pub mod receiver;
 use self::receiver::Receiver;
 
+#[doc(hidden)]
+pub mod instance;
+use self::instance::GlobalInstance;
+
+mod builder;
+pub use self::builder::MtLoggerBuilder;
+
+mod filter;
+use self::filter::{Directives, TargetFilter};
+
+mod logfile;
+
+#[cfg(feature = "gzip")]
+mod compress;
+
+#[cfg(target_os = "linux")]
+mod signal;
+
+mod sink;
+pub use self::sink::{ConsoleSink, FileSink, RecordFormat, Sink, SinkId};
+
+mod guard;
+pub use self::guard::MtLoggerGuard;
+
 
 ///////////////////////////////////////////////////////////////////////////////
 //  Data Structures
@@ -79,20 +125,78 @@ pub enum Level {
     Fatal = 0x20,
 }
 
-#[doc(hidden)]
-/// Tuple struct containing log message and its log level
+/// Set of [`Level`]s to be logged, for selecting levels other than a minimum threshold.
+///
+/// Created by combining levels with `|`, or from the associated constructors.
+///
+/// # Examples
+///
+/// Select only `Trace` and `Error` messages for a targeted investigation.
+/// ```
+/// # use mt_logger::{Level, LevelMask};
+/// let mask = Level::Trace | Level::Error;
+/// assert!(mask.contains(Level::Trace));
+/// assert!(!mask.contains(Level::Debug));
+/// ```
+///
+/// Select every `Debug`-level message and higher, except for `Info`.
+/// ```
+/// # use mt_logger::{Level, LevelMask};
+/// let mask = LevelMask::at_or_above(Level::Debug).without(Level::Info);
+/// assert_eq!(Level::Debug | Level::Warning | Level::Error | Level::Fatal, mask);
+/// ```
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct LevelMask(u8);
+
+/// Typed value of a structured field attached to a log message, see [`mt_log!`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    /// Signed integer value
+    Int(i64),
+    /// Unsigned integer value
+    UInt(u64),
+    /// Floating-point value
+    Float(f64),
+    /// Boolean value
+    Bool(bool),
+    /// String value
+    Str(String),
+    /// Value captured via its [`Debug`](fmt::Debug) implementation, using the `?value` syntax
+    Debug(String),
+}
+
+/// Log message and its metadata, as passed to each [`Sink`].
 pub struct MsgTuple {
+    /// Time at which the message was logged
     pub timestamp: DateTime<Local>,
+    /// Level of the message
     pub level: Level,
+    /// Target of the message, the module path of the logging call unless overridden
+    pub target: String,
+    /// Fully-qualified name of the function that logged the message
     pub fn_name: String,
+    /// Line number of the logging call
     pub line: u32,
+    /// Column number of the logging call
+    pub column: u32,
+    /// Source file of the logging call, empty if unknown
+    pub file: &'static str,
+    /// Module path of the logging call, empty if unknown
+    pub module_path: &'static str,
+    /// Name of the thread that logged the message, if it has one
+    pub thread_name: Option<String>,
+    /// ID of the thread that logged the message
+    pub thread_id: ThreadId,
+    /// Formatted message content
     pub msg: String,
+    /// Structured fields, in the order they were given
+    pub fields: Vec<(&'static str, FieldValue)>,
 }
 
 /// Specifies which stream(s) log messages should be written to.
 #[derive(Debug, Copy, Clone)]
 pub enum OutputStream {
-    /// Don't write to either stream, i.e., disable logging
+    /// Don't write to any stream, i.e., disable logging
     Neither = 0x0,
     /// Write only to StdOut
     StdOut = 0x1,
@@ -100,15 +204,103 @@ pub enum OutputStream {
     File = 0x2,
     /// Write to both StdOut and a File
     Both = 0x3,
+    /// Write only to StdErr
+    StdErr = 0x4,
+    /// Write to both StdErr and a File
+    StdErrAndFile = 0x6,
+    /// Write `Warning`-level messages and higher to StdErr, and lower levels to StdOut
+    Split = 0x8,
+    /// Write to a File, and split messages between StdErr and StdOut as for `OutputStream::Split`
+    SplitAndFile = 0xA,
+}
+
+/// Specifies what happens when a message is logged while the calling thread's queue to the logging
+/// thread is full, see [`MtLoggerBuilder::channel_capacity`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum OverflowPolicy {
+    /// Block the calling thread until there is room in its queue
+    Block,
+    /// Discard the message being logged
+    DropNewest,
+    /// Discard the oldest queued message to make room for the message being logged
+    DropOldest,
+    /// Block the calling thread until there is room in its queue or the timeout elapses,
+    /// then discard the message being logged
+    BlockTimeout(Duration),
+}
+
+/// Specifies when the logfile is synced to disk, guaranteeing that recorded messages survive a crash.
+///
+/// Regardless of policy, messages are written to the logfile in batches whenever the logging
+/// thread runs out of queued messages, and when [`mt_flush!`] is called.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DurabilityPolicy {
+    /// Never sync, leaving it up to the operating system
+    Never,
+    /// Sync after recording an `Error` or `Fatal` message, and on [`mt_flush!`]
+    OnError,
+    /// Sync at most once per interval while messages are being recorded, and on [`mt_flush!`]
+    Interval(Duration),
+    /// Sync after recording every message
+    EveryMessage,
+}
+
+/// Specifies how the logfile is replaced when it is rotated.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum RotationStyle {
+    /// Rename the logfile and any previously-rotated logfiles with an increasing index, e.g.,
+    /// `app.log` to `app.log.1` and `app.log.1` to `app.log.2`, then recreate it
+    Indexed,
+    /// Create a new logfile named from the logfile name template at the time of rotation, which
+    /// should include the `{timestamp}` placeholder. Falls back to `RotationStyle::Indexed` if the
+    /// name would not change.
+    Timestamped,
+}
+
+/// Specifies the calendar period after which the logfile is rotated.
+///
+/// Periods start on local or UTC boundaries, see [`MtLoggerBuilder::rotation_utc`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum RotationInterval {
+    /// Rotate at the start of every hour
+    Hourly,
+    /// Rotate at midnight
+    Daily,
+    /// Rotate at midnight between Sunday and Monday
+    Weekly,
+    /// Rotate whenever a multiple of the given interval has elapsed since midnight on January 1st,
+    /// 1970, e.g., every 6 hours at 00:00, 06:00, 12:00, and 18:00. Rounded down to whole seconds,
+    /// with a minimum of one second.
+    Custom(Duration),
+}
+
+/// Signals that reopen the logfiles when received, see [`MtLoggerBuilder::reopen_on_signal`]
+#[cfg(target_os = "linux")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReopenSignal {
+    /// `SIGHUP`, as sent by most logrotate configurations
+    Hangup,
+    /// `SIGUSR1`, for processes that use `SIGHUP` for something else
+    User1,
 }
 
 #[doc(hidden)]
 /// Enumeration of commands that the logging thread will handle
 pub enum Command {
-    LogMsg(MsgTuple),
+    LogMsg(Box<MsgTuple>),
     SetOutputLevel(Level),
+    SetLevelMask(LevelMask),
+    SetTargetLevel(String, Level),
+    SetTargetLevelMask(String, LevelMask),
     SetOutputStream(OutputStream),
+    SetStreamLevel(OutputStream, Level),
+    SetStreamLevelMask(OutputStream, LevelMask),
+    SetSinkLevelMask(SinkId, LevelMask),
+    AddSink(SinkId, Box<dyn Sink>),
+    RemoveSink(SinkId),
+    ReopenFiles,
     Flush(mpsc::Sender<()>),
+    Shutdown(mpsc::Sender<()>),
 }
 
 #[doc(hidden)]
@@ -116,7 +308,10 @@ pub enum Command {
 pub struct MtLogger {
     enabled: bool,
     sender: Sender,
+    level_mask: Arc<AtomicU8>,
+    target_filter: Arc<Mutex<TargetFilter>>,
     msg_count: Arc<AtomicU64>,
+    receiver_handle: Arc<Mutex<Option<JoinHandle<()>>>>,
 }
 
 /// Logging errors
@@ -124,8 +319,16 @@ pub struct MtLogger {
 pub enum MtLoggerError {
     /// A logging command was attempted before the global logger instance was initialized with [`mt_new!`]
     LoggerNotInitialized,
+    /// An attempt was made to initialize the global logger instance more than once
+    AlreadyInitialized,
+    /// The logging thread terminated abnormally and could not be joined
+    ReceiverDied,
+    /// A filter directive or level name could not be parsed, contains the offending directive
+    InvalidDirective(String),
 
     /* Wrappers */
+    /// Wrapper for `io::Error`, e.g., when the log directory or logfile cannot be created
+    Io(io::Error),
     /// Wrapper for `SendError<>`
     SendError(SendError<Command>),
     /// Wrapper for `RecvError`
@@ -133,48 +336,180 @@ pub enum MtLoggerError {
 }
 
 #[doc(hidden)]
-pub static INSTANCE: OnceCell<MtLogger> = OnceCell::new();
+pub static INSTANCE: GlobalInstance = GlobalInstance::new();
 
 
 ///////////////////////////////////////////////////////////////////////////////
 //  Object Implementation
 ///////////////////////////////////////////////////////////////////////////////
 
+impl LevelMask {
+    /// Creates a mask containing no levels, i.e., disables logging
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// Creates a mask containing every level
+    pub const fn all() -> Self {
+        Self(ALL_LEVELS)
+    }
+
+    /// Creates a mask containing the given level and every level above it, equivalent to using
+    /// the level as a minimum threshold
+    pub const fn at_or_above(level: Level) -> Self {
+        Self(!(level as u8 - 1) & ALL_LEVELS)
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Accessor Methods  *
+    \*  *  *  *  *  *  *  */
+
+    /// Returns `true` if the mask contains the given level
+    pub const fn contains(self, level: Level) -> bool {
+        self.0 & level as u8 != 0
+    }
+
+    /// Returns `true` if the mask contains no levels
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    #[doc(hidden)]
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Builder Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Returns a copy of the mask with the given level added
+    pub const fn with(self, level: Level) -> Self {
+        Self(self.0 | level as u8)
+    }
+
+    /// Returns a copy of the mask with the given level removed
+    pub const fn without(self, level: Level) -> Self {
+        Self(self.0 & !(level as u8))
+    }
+}
+
+impl OutputStream {
+    /// Returns the built-in sinks written to by the stream(s)
+    pub(crate) fn sink_ids(self) -> Vec<SinkId> {
+        let mut sink_ids = Vec::new();
+        if self as u8 & (OutputStream::StdOut as u8 | OutputStream::Split as u8) != 0 {
+            sink_ids.push(SinkId::CONSOLE);
+        }
+        if self as u8 & OutputStream::File as u8 != 0 {
+            sink_ids.push(SinkId::FILE);
+        }
+        if self as u8 & (OutputStream::StdErr as u8 | OutputStream::Split as u8) != 0 {
+            sink_ids.push(SinkId::STDERR);
+        }
+
+        sink_ids
+    }
+
+    /// Checks whether a message of the given level is written to the given built-in sink
+    pub(crate) fn writes_to(self, sink_id: SinkId, level: Level) -> bool {
+        let split = self as u8 & OutputStream::Split as u8 != 0;
+        match sink_id {
+            SinkId::CONSOLE => {
+                self as u8 & OutputStream::StdOut as u8 != 0
+                    || (split && level < SPLIT_STDERR_LEVEL)
+            }
+            SinkId::FILE => self as u8 & OutputStream::File as u8 != 0,
+            SinkId::STDERR => {
+                self as u8 & OutputStream::StdErr as u8 != 0
+                    || (split && level >= SPLIT_STDERR_LEVEL)
+            }
+            _ => true,
+        }
+    }
+}
+
 impl MtLogger {
     /// Fully-qualified constructor
+    ///
+    /// # Panics
+    ///
+    /// Panics if the logfile cannot be created, see [`MtLogger::try_new`] for a fallible version.
     pub fn new(
         logfile_prefix: &'static str,
         output_level: Level,
         output_stream: OutputStream,
     ) -> Self {
+        MtLoggerBuilder::new(logfile_prefix)
+            .output_level(output_level)
+            .output_stream(output_stream)
+            .build()
+    }
+
+    /// Fully-qualified fallible constructor
+    pub fn try_new(
+        logfile_prefix: &'static str,
+        output_level: Level,
+        output_stream: OutputStream,
+    ) -> Result<Self, MtLoggerError> {
+        MtLoggerBuilder::new(logfile_prefix)
+            .output_level(output_level)
+            .output_stream(output_stream)
+            .try_build()
+    }
+
+    /// Constructor from a fully-specified builder
+    fn from_builder(mut builder: MtLoggerBuilder) -> Result<Self, MtLoggerError> {
+        // Apply any filter directives on top of the configured levels before anything is created
+        if let Some(directives) = &builder.directives {
+            let Directives {
+                default_level,
+                target_levels,
+            } = filter::parse_directives(directives)?;
+            if let Some(default_level) = default_level {
+                builder.output_mask = LevelMask::at_or_above(default_level);
+            }
+            builder.target_masks.extend(
+                target_levels
+                    .into_iter()
+                    .map(|(target, level)| (target, LevelMask::at_or_above(level))),
+            );
+        }
+
         // Create the log messaging and control channel
-        // Must be a sync channel in order to wrap OnceCell around an MtLogger
-        let (logger_tx, logger_rx) = mpsc::sync_channel::<Command>(CHANNEL_SIZE);
+        let (logger_tx, logger_rx) =
+            channel::channel(builder.channel_capacity, builder.overflow_policy);
 
-        // Create the shared message count
+        // Create the shared message count and filter
         let msg_count = Arc::new(AtomicU64::new(0));
+        let target_filter = TargetFilter::new(builder.output_mask, &builder.target_masks);
+        let level_mask = Arc::new(AtomicU8::new(target_filter.union_mask().bits()));
+        let target_filter = Arc::new(Mutex::new(target_filter));
 
-        // Initialize receiver struct, build and spawn thread
-        let mut log_receiver = Receiver::new(
-            logfile_prefix,
+        // Initialize receiver struct on this thread so file errors reach the caller, then spawn thread
+        let log_receiver = Receiver::new(
+            &mut builder,
             logger_rx,
-            output_level,
-            output_stream,
+            Arc::clone(&target_filter),
             Arc::clone(&msg_count),
-        );
-        thread::Builder::new()
-            .name("log_receiver".to_string())
-            .spawn(move || log_receiver.main())
-            .unwrap();
+        )?;
+        let receiver_handle = thread::Builder::new()
+            .name(builder.thread_name)
+            .spawn(move || log_receiver.main())?;
 
         // Initialize sender struct
         let log_sender = Sender::new(logger_tx);
 
-        Self {
+        Ok(Self {
             enabled: true,
             sender: log_sender,
+            level_mask,
+            target_filter,
             msg_count,
-        }
+            receiver_handle: Arc::new(Mutex::new(Some(receiver_handle))),
+        })
     }
 
 
@@ -187,13 +522,29 @@ impl MtLogger {
         self.msg_count.load(Ordering::SeqCst)
     }
 
+    #[doc(hidden)]
+    // Mirrors every level the receiver records so filtered messages are never formatted or sent
+    pub fn is_enabled(&self, level: Level) -> bool {
+        self.enabled && level as u8 & self.level_mask.load(Ordering::Relaxed) != 0
+    }
+
 
     /*  *  *  *  *  *  *  *\
      *   Utility Methods  *
     \*  *  *  *  *  *  *  */
 
     #[doc(hidden)]
-    //FEAT: Bring filtering back to the sending-side
+    pub fn log_tuple(&self, log_tuple: MsgTuple) -> Result<(), SendError<Command>> {
+        // If logging is enabled for this level, send the pre-packaged tuple
+        if self.is_enabled(log_tuple.level) {
+            self.sender.send_log(Command::LogMsg(Box::new(log_tuple)))
+        } else {
+            Ok(())
+        }
+    }
+
+    #[doc(hidden)]
+    #[track_caller]
     pub fn log_msg(
         &self,
         timestamp: DateTime<Local>,
@@ -202,16 +553,26 @@ impl MtLogger {
         line: u32,
         msg: String,
     ) -> Result<(), SendError<Command>> {
-        // If logging is enabled, package log message into tuple and send
-        if self.enabled {
+        // If logging is enabled for this level, package log message into tuple and send
+        if self.is_enabled(level) {
+            // Without the macro, only the caller's file and column are known
+            let caller = Location::caller();
+            let thread = thread::current();
             let log_tuple = MsgTuple {
                 timestamp,
                 level,
+                target: String::new(),
                 fn_name,
                 line,
+                column: caller.column(),
+                file: caller.file(),
+                module_path: "",
+                thread_name: thread.name().map(String::from),
+                thread_id: thread.id(),
                 msg,
+                fields: Vec::new(),
             };
-            self.sender.send_log(Command::LogMsg(log_tuple))
+            self.sender.send_log(Command::LogMsg(Box::new(log_tuple)))
         } else {
             Ok(())
         }
@@ -220,12 +581,82 @@ impl MtLogger {
     #[doc(hidden)]
     pub fn log_cmd(&self, cmd: Command) -> Result<(), SendError<Command>> {
         if self.enabled {
+            // Update the sending-side filter immediately. The receiver takes the updated filter
+            // once the command reaches it, so the lock need not be held while sending.
+            if let Command::SetOutputLevel(_)
+            | Command::SetLevelMask(_)
+            | Command::SetTargetLevel(..)
+            | Command::SetTargetLevelMask(..) = cmd
+            {
+                let mut target_filter = self
+                    .target_filter
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+                match &cmd {
+                    Command::SetOutputLevel(output_level) => {
+                        target_filter.set_default_mask(LevelMask::at_or_above(*output_level))
+                    }
+                    Command::SetLevelMask(output_mask) => {
+                        target_filter.set_default_mask(*output_mask)
+                    }
+                    Command::SetTargetLevel(target, level) => {
+                        target_filter.set_target_mask(target, LevelMask::at_or_above(*level))
+                    }
+                    Command::SetTargetLevelMask(target, mask) => {
+                        target_filter.set_target_mask(target, *mask)
+                    }
+                    _ => (),
+                }
+                self.level_mask
+                    .store(target_filter.union_mask().bits(), Ordering::Relaxed);
+            }
+
             self.sender.send_cmd(cmd)
         } else {
             Ok(())
         }
     }
 
+    #[doc(hidden)]
+    pub fn apply_directives(&self, directives: &str) -> Result<(), MtLoggerError> {
+        // Parse everything first, so an invalid directive leaves the filter untouched
+        let Directives {
+            default_level,
+            target_levels,
+        } = filter::parse_directives(directives)?;
+
+        if let Some(default_level) = default_level {
+            self.log_cmd(Command::SetOutputLevel(default_level))?;
+        }
+        for (target, level) in target_levels {
+            self.log_cmd(Command::SetTargetLevel(target, level))?;
+        }
+
+        Ok(())
+    }
+
+    #[doc(hidden)]
+    pub fn add_sink(&self, sink: Box<dyn Sink>) -> Result<SinkId, MtLoggerError> {
+        let sink_id = SinkId::unique();
+        self.log_cmd(Command::AddSink(sink_id, sink))?;
+
+        Ok(sink_id)
+    }
+
+    #[doc(hidden)]
+    pub fn remove_sink(&self, sink_id: SinkId) -> Result<(), MtLoggerError> {
+        self.log_cmd(Command::RemoveSink(sink_id))?;
+
+        Ok(())
+    }
+
+    #[doc(hidden)]
+    pub fn reopen_files(&self) -> Result<(), MtLoggerError> {
+        self.log_cmd(Command::ReopenFiles)?;
+
+        Ok(())
+    }
+
     #[doc(hidden)]
     pub fn flush(&self) -> Result<(), MtLoggerError> {
         // Create a channel that will be used to notify completion of the flush
@@ -239,6 +670,36 @@ impl MtLogger {
 
         Ok(())
     }
+
+    #[doc(hidden)]
+    pub fn shutdown(&self) -> Result<(), MtLoggerError> {
+        // Readers never free replaced instances, so take the opportunity to do so here
+        instance::reclaim();
+
+        // Hold the handle lock for the duration so concurrent shutdowns are serialized
+        let mut receiver_handle = self
+            .receiver_handle
+            .lock()
+            .map_err(|_| MtLoggerError::ReceiverDied)?;
+
+        // If the handle has already been taken, the logging thread is already gone
+        let handle = match receiver_handle.take() {
+            Some(handle) => handle,
+            None => return Ok(()),
+        };
+
+        // Create a channel that will be used to notify completion of the shutdown
+        let (shutdown_ack_tx, shutdown_ack_rx) = mpsc::channel::<()>();
+
+        // Send a shutdown command to the receiver thread
+        self.sender.send_cmd(Command::Shutdown(shutdown_ack_tx))?;
+
+        // Block until the shutdown ACK arrives, then wait for the thread to exit
+        shutdown_ack_rx.recv()?;
+        handle.join().map_err(|_| MtLoggerError::ReceiverDied)?;
+
+        Ok(())
+    }
 }
 
 
@@ -246,6 +707,61 @@ impl MtLogger {
 //  Static Functions
 ///////////////////////////////////////////////////////////////////////////////
 
+// Resolves the max level cargo features, with release features taking precedence in release
+// builds, and the most restrictive feature winning if several are enabled
+const fn static_max_level() -> u8 {
+    if cfg!(all(
+        not(debug_assertions),
+        feature = "release_max_level_off"
+    )) {
+        LEVEL_OFF
+    } else if cfg!(all(
+        not(debug_assertions),
+        feature = "release_max_level_fatal"
+    )) {
+        Level::Fatal as u8
+    } else if cfg!(all(
+        not(debug_assertions),
+        feature = "release_max_level_error"
+    )) {
+        Level::Error as u8
+    } else if cfg!(all(
+        not(debug_assertions),
+        feature = "release_max_level_warning"
+    )) {
+        Level::Warning as u8
+    } else if cfg!(all(
+        not(debug_assertions),
+        feature = "release_max_level_info"
+    )) {
+        Level::Info as u8
+    } else if cfg!(all(
+        not(debug_assertions),
+        feature = "release_max_level_debug"
+    )) {
+        Level::Debug as u8
+    } else if cfg!(all(
+        not(debug_assertions),
+        feature = "release_max_level_trace"
+    )) {
+        Level::Trace as u8
+    } else if cfg!(feature = "max_level_off") {
+        LEVEL_OFF
+    } else if cfg!(feature = "max_level_fatal") {
+        Level::Fatal as u8
+    } else if cfg!(feature = "max_level_error") {
+        Level::Error as u8
+    } else if cfg!(feature = "max_level_warning") {
+        Level::Warning as u8
+    } else if cfg!(feature = "max_level_info") {
+        Level::Info as u8
+    } else if cfg!(feature = "max_level_debug") {
+        Level::Debug as u8
+    } else {
+        Level::Trace as u8
+    }
+}
+
 #[doc(hidden)]
 // Wrapper around chrono::Local::now() to avoid dependency issues with using external crate functions in macros
 pub fn mt_now() -> DateTime<Local> {
@@ -275,6 +791,135 @@ impl fmt::Display for Level {
 }
 
 
+impl FromStr for Level {
+    type Err = MtLoggerError;
+
+    /// Parses a level name case-insensitively, accepting `warn` as an alias for `warning`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "trace" => Ok(Self::Trace),
+            "debug" => Ok(Self::Debug),
+            "info" => Ok(Self::Info),
+            "warning" | "warn" => Ok(Self::Warning),
+            "error" => Ok(Self::Error),
+            "fatal" => Ok(Self::Fatal),
+            _ => Err(MtLoggerError::InvalidDirective(s.to_string())),
+        }
+    }
+}
+
+
+impl BitOr for Level {
+    type Output = LevelMask;
+
+    fn bitor(self, rhs: Self) -> LevelMask {
+        LevelMask::from(self).with(rhs)
+    }
+}
+
+
+/*  *  *  *  *  *  *  *\
+ *     LevelMask      *
+\*  *  *  *  *  *  *  */
+
+impl From<Level> for LevelMask {
+    fn from(src: Level) -> Self {
+        Self(src as u8)
+    }
+}
+
+impl BitOr for LevelMask {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOr<Level> for LevelMask {
+    type Output = Self;
+
+    fn bitor(self, rhs: Level) -> Self {
+        self.with(rhs)
+    }
+}
+
+impl BitOrAssign<Level> for LevelMask {
+    fn bitor_assign(&mut self, rhs: Level) {
+        *self = self.with(rhs);
+    }
+}
+
+
+/*  *  *  *  *  *  *  *\
+ *     FieldValue     *
+\*  *  *  *  *  *  *  */
+
+impl fmt::Display for FieldValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Int(value) => write!(f, "{}", value),
+            Self::UInt(value) => write!(f, "{}", value),
+            Self::Float(value) => write!(f, "{}", value),
+            Self::Bool(value) => write!(f, "{}", value),
+            // Quote strings only when needed to keep `key=value` pairs unambiguous
+            Self::Str(value) | Self::Debug(value) => {
+                if value.is_empty()
+                    || value
+                        .chars()
+                        .any(|c| c.is_whitespace() || c == '=' || c == '"')
+                {
+                    write!(f, "{:?}", value)
+                } else {
+                    write!(f, "{}", value)
+                }
+            }
+        }
+    }
+}
+
+/// Implements `From<$src>` for [`FieldValue`] by converting into the given variant
+macro_rules! impl_from_for_field_value {
+    ($variant:ident($dest:ty): $( $src:ty ),*) => {
+        $(
+            impl From<$src> for FieldValue {
+                fn from(src: $src) -> Self {
+                    Self::$variant(src as $dest)
+                }
+            }
+        )*
+    };
+}
+impl_from_for_field_value!(Int(i64): i8, i16, i32, i64, isize);
+impl_from_for_field_value!(UInt(u64): u8, u16, u32, u64, usize);
+impl_from_for_field_value!(Float(f64): f32, f64);
+impl From<bool> for FieldValue {
+    fn from(src: bool) -> Self {
+        Self::Bool(src)
+    }
+}
+impl From<&str> for FieldValue {
+    fn from(src: &str) -> Self {
+        Self::Str(src.to_string())
+    }
+}
+impl From<String> for FieldValue {
+    fn from(src: String) -> Self {
+        Self::Str(src)
+    }
+}
+impl From<&String> for FieldValue {
+    fn from(src: &String) -> Self {
+        Self::Str(src.clone())
+    }
+}
+impl From<char> for FieldValue {
+    fn from(src: char) -> Self {
+        Self::Str(src.to_string())
+    }
+}
+
+
 /*  *  *  *  *  *  *  *\
  *    MtLoggerError   *
 \*  *  *  *  *  *  *  */
@@ -290,8 +935,27 @@ impl fmt::Display for MtLoggerError {
                     "Attempted a command before the logger instance was initialized"
                 )
             }
+            Self::AlreadyInitialized => {
+                write!(
+                    f,
+                    "Attempted to initialize an already-initialized logger instance"
+                )
+            }
+            Self::ReceiverDied => {
+                write!(f, "The logging thread terminated abnormally")
+            }
+            Self::InvalidDirective(directive) => {
+                write!(f, "Invalid filter directive '{}'", directive)
+            }
 
             // Wrappers
+            Self::Io(io_err) => {
+                write!(
+                    f,
+                    "Encountered io::Error '{}' while initializing the logger",
+                    io_err
+                )
+            }
             Self::SendError(send_err) => {
                 write!(
                     f,
@@ -310,6 +974,11 @@ impl fmt::Display for MtLoggerError {
     }
 }
 
+impl From<io::Error> for MtLoggerError {
+    fn from(src: io::Error) -> Self {
+        Self::Io(src)
+    }
+}
 impl From<SendError<Command>> for MtLoggerError {
     fn from(src: SendError<Command>) -> Self {
         Self::SendError(src)
@@ -349,6 +1018,12 @@ impl From<RecvError> for MtLoggerError {
 /// mt_new!(Some("my_app_v2.3"), Level::Trace, OutputStream::File);
 /// # }
 /// ```
+///
+/// Filter directives are read from the `MT_LOG` environment variable if it is set, overriding
+/// `$output_level`, e.g., `MT_LOG="info,my_app::net=trace"`. See [`MtLoggerBuilder::directives`]
+/// for the format.
+///
+/// For additional configuration options, see [`MtLoggerBuilder`].
 #[macro_export]
 macro_rules! mt_new {
     ($logfile_prefix:expr, $output_level:expr, $output_stream:expr) => {{
@@ -358,86 +1033,312 @@ macro_rules! mt_new {
             None => env!("CARGO_PKG_NAME"),
         };
 
-        let logger = $crate::MtLogger::new(prefix, $output_level, $output_stream);
-
-        $crate::INSTANCE
-            .set(logger)
-            .expect("MtLogger INSTANCE already initialized");
+        $crate::MtLoggerBuilder::new(prefix)
+            .output_level($output_level)
+            .output_stream($output_stream)
+            .env_directives($crate::DIRECTIVES_ENV_VAR)
+            .install();
     }};
 }
 
-/// Sends a message to be logged at the specified logging level.
-///
-/// Arguments after `$log_level` follow the format of [`println!`] arguments.
-///
-/// # Note
-/// A call to this macro will only _send_ the message to the logging thread.
-/// It does NOT guarantee that the message will be delivered at any time.
+/// Initializes the `mt_logger` global instance, reporting any failure to the caller.
 ///
-/// If all messages must be logged at a given time, see [`mt_flush!`].
+/// Takes the same arguments as [`mt_new!`], but returns [`Result<(), MtLoggerError>`] rather than
+/// panicking.
 ///
 /// # Examples
 ///
-/// Logs a `Debug`-level message with the content, "No response received after 500ms".
+/// Initialize the logger instance, handling the case where it was already initialized.
 /// ```
 /// # #[macro_use] extern crate mt_logger;
-/// # use mt_logger::Level;
-/// # fn main() {
-/// let timeout = 500;
-/// mt_log!(Level::Debug, "No response received after {}ms", timeout);
+/// # use mt_logger::{Level, MtLoggerError, OutputStream};
+/// # fn main() -> Result<(), MtLoggerError> {
+/// try_mt_new!(None, Level::Info, OutputStream::Both)?;
+///
+/// match try_mt_new!(None, Level::Info, OutputStream::Both) {
+///     Err(MtLoggerError::AlreadyInitialized) => (),
+///     _ => panic!("Second initialization should fail"),
+/// }
+/// # Ok(())
 /// # }
 /// ```
+///
+/// # Errors
+///
+/// Returns [`MtLoggerError::AlreadyInitialized`] if the global instance has already been
+/// initialized, [`MtLoggerError::InvalidDirective`] if the `MT_LOG` environment variable contains
+/// an invalid directive, or [`MtLoggerError::Io`] if the log directory, logfile, or logging thread
+/// could not be created.
+///
+/// See [`MtLoggerError`] for an enumeration of errors that may be returned.
 #[macro_export]
-macro_rules! mt_log {
-    ($log_level:expr, $( $fmt_args:expr ),*) => {{
-        // Take the timestamp first for highest accuracy
-        let timestamp = $crate::mt_now();
-
-        // Capture fully-qualified function name
-        let fn_name = {
-            fn f() {}
-            fn type_name_of<T>(_: T) -> &'static str {
-                std::any::type_name::<T>()
-            }
-            let name = type_name_of(f);
-            &name[..name.len() - 3]
+macro_rules! try_mt_new {
+    ($logfile_prefix:expr, $output_level:expr, $output_stream:expr) => {{
+        // Use prefix if specified, or default to parent package name
+        let prefix = match $logfile_prefix {
+            Some(specified_prefix) => specified_prefix,
+            None => env!("CARGO_PKG_NAME"),
         };
 
-        let msg_content: String = format!($( $fmt_args ),*);
-
-        $crate::INSTANCE
-            .get()
-            // If None is encountered, the logger has not been initialized, so do nothing
-            .and_then(|instance| instance.log_msg(
-                timestamp,
-                $log_level,
-                fn_name.to_string(),
-                line!(),
-                msg_content)
-                .ok()
-            );
+        $crate::MtLoggerBuilder::new(prefix)
+            .output_level($output_level)
+            .output_stream($output_stream)
+            .env_directives($crate::DIRECTIVES_ENV_VAR)
+            .try_install()
     }};
 }
 
-/// Sets the active stream to the specified [`OutputStream`].
+/// Initializes the `mt_logger` global instance, returning a guard that flushes it when dropped.
+///
+/// Takes the same arguments as [`mt_new!`]. Binding the returned [`MtLoggerGuard`] at the top of
+/// `main` guarantees that every message is recorded before the program exits, without having to
+/// call [`mt_flush!`] on every return path.
 ///
 /// # Examples
 ///
-/// Set active stream to `StdOut`.
+/// Initialize the logger instance for the duration of `main`.
 /// ```
 /// # #[macro_use] extern crate mt_logger;
-/// # use mt_logger::OutputStream;
-/// # fn main() {
-/// mt_stream!(OutputStream::StdOut);
-/// # }
+/// # use mt_logger::{Level, OutputStream};
+/// fn main() {
+///     let _guard = mt_new_guard!(None, Level::Info, OutputStream::Both);
+///
+///     mt_log!(Level::Info, "This message is recorded before main() returns.");
+/// }
 /// ```
 ///
-/// Set active stream to `Neither`, i.e., disable logging.
+/// Shut the logger down entirely when the guard is dropped.
 /// ```
 /// # #[macro_use] extern crate mt_logger;
-/// # use mt_logger::OutputStream;
+/// # use mt_logger::{Level, OutputStream};
 /// # fn main() {
-/// mt_stream!(OutputStream::Neither);
+/// let _guard = mt_new_guard!(None, Level::Info, OutputStream::Both).shutdown_on_drop();
+/// # }
+/// ```
+///
+/// # Panics
+///
+/// Panics under the same conditions as [`mt_new!`].
+#[macro_export]
+macro_rules! mt_new_guard {
+    ($logfile_prefix:expr, $output_level:expr, $output_stream:expr) => {{
+        // Use prefix if specified, or default to parent package name
+        let prefix = match $logfile_prefix {
+            Some(specified_prefix) => specified_prefix,
+            None => env!("CARGO_PKG_NAME"),
+        };
+
+        $crate::MtLoggerBuilder::new(prefix)
+            .output_level($output_level)
+            .output_stream($output_stream)
+            .env_directives($crate::DIRECTIVES_ENV_VAR)
+            .install_with_guard()
+    }};
+}
+
+/// Replaces the `mt_logger` global instance with a newly-initialized one.
+///
+/// Takes the same arguments as [`mt_new!`]. The new instance is installed before the previous
+/// instance (if any) is shut down, so messages sent before the replacement are recorded by the
+/// previous instance, and messages sent after it by the new instance. However, a thread that
+/// fetched the previous instance just before the replacement may still send to it after it has
+/// shut down, in which case the message may be dropped. The previous instance is freed right away
+/// if no other thread is using it, or otherwise by a later replacement or shutdown, so instances
+/// can be replaced repeatedly, e.g., once per test, without leaking memory.
+///
+/// Returns [`Result<(), MtLoggerError>`]
+///
+/// # Examples
+///
+/// Switch to a new logfile with a different prefix and level.
+/// ```
+/// # #[macro_use] extern crate mt_logger;
+/// # use mt_logger::{Level, MtLoggerError, OutputStream};
+/// # fn main() -> Result<(), MtLoggerError> {
+/// mt_new!(None, Level::Info, OutputStream::Both);
+/// mt_log!(Level::Info, "This message is written to the first logfile.");
+///
+/// mt_replace!(Some("second_log"), Level::Debug, OutputStream::File)?;
+/// mt_log!(Level::Debug, "This message is written to the second logfile.");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Returns [`MtLoggerError::Io`] if the new instance could not be created, in which case the
+/// previous instance remains installed. Errors encountered while shutting down the previous
+/// instance are also propagated back to the caller, see [`mt_shutdown!`].
+///
+/// See [`MtLoggerError`] for an enumeration of errors that may be returned.
+#[macro_export]
+macro_rules! mt_replace {
+    ($logfile_prefix:expr, $output_level:expr, $output_stream:expr) => {{
+        // Use prefix if specified, or default to parent package name
+        let prefix = match $logfile_prefix {
+            Some(specified_prefix) => specified_prefix,
+            None => env!("CARGO_PKG_NAME"),
+        };
+
+        $crate::MtLoggerBuilder::new(prefix)
+            .output_level($output_level)
+            .output_stream($output_stream)
+            .env_directives($crate::DIRECTIVES_ENV_VAR)
+            .try_replace()
+    }};
+}
+
+/// Sends a message to be logged at the specified logging level.
+///
+/// Arguments after `$log_level` follow the format of [`println!`] arguments. They may be followed
+/// by a `;` and a comma-separated list of `key = value` structured fields, which are carried on the
+/// message as typed [`FieldValue`]s and rendered as `key=value` after the message content. Values
+/// may be any type convertible into a [`FieldValue`], or any [`Debug`](std::fmt::Debug) type when
+/// written as `key = ?value`.
+///
+/// Each message has a target, which defaults to the calling module's path and may be overridden
+/// with a leading `target:` argument. Targets can be given their own minimum level via
+/// [`mt_level!`] or [`MtLoggerBuilder::target_level`].
+///
+/// Messages below the active logging level (see [`mt_level!`]) are discarded before the
+/// arguments are formatted, so filtered calls are nearly free. Messages below the level selected
+/// by the `max_level_*` or `release_max_level_*` cargo features are removed at compile time,
+/// though their arguments are still type-checked.
+///
+/// # Note
+/// A call to this macro will only _send_ the message to the logging thread.
+/// It does NOT guarantee that the message will be delivered at any time.
+///
+/// If all messages must be logged at a given time, see [`mt_flush!`].
+///
+/// # Examples
+///
+/// Logs a `Debug`-level message with the content, "No response received after 500ms".
+/// ```
+/// # #[macro_use] extern crate mt_logger;
+/// # use mt_logger::Level;
+/// # fn main() {
+/// let timeout = 500;
+/// mt_log!(Level::Debug, "No response received after {}ms", timeout);
+/// # }
+/// ```
+///
+/// Logs an `Info`-level message with structured fields, recorded as
+/// `request done user_id=42 ok=true latency_ms=3.2 path="/index.html"`.
+/// ```
+/// # #[macro_use] extern crate mt_logger;
+/// # use mt_logger::Level;
+/// # fn main() {
+/// let path = std::path::Path::new("/index.html");
+/// mt_log!(Level::Info, "request done"; user_id = 42, ok = true, latency_ms = 3.2, path = ?path);
+/// # }
+/// ```
+///
+/// Logs a `Trace`-level message for the `net::tcp` target.
+/// ```
+/// # #[macro_use] extern crate mt_logger;
+/// # use mt_logger::Level;
+/// # fn main() {
+/// mt_log!(target: "net::tcp", Level::Trace, "Connection opened");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! mt_log {
+    (target: $target:expr, $log_level:expr, $( $fmt_args:expr ),*) => {
+        $crate::mt_log!(target: $target, $log_level, $( $fmt_args ),*;)
+    };
+    (target: $target:expr, $log_level:expr, $( $fmt_args:expr ),*; $( $fields:tt )*) => {{
+        let log_level = $log_level;
+
+        // Messages below the compiled maximum level are optimized away entirely
+        if (log_level as u8) >= $crate::STATIC_MAX_LEVEL {
+            // If None is encountered, the logger has not been initialized, so do nothing.
+            // Messages below the active level are discarded before any formatting takes place.
+            if let Some(instance) = $crate::INSTANCE
+                .get()
+                .filter(|instance| instance.is_enabled(log_level))
+            {
+                // Take the timestamp first for highest accuracy
+                let timestamp = $crate::mt_now();
+
+                // Capture fully-qualified function name
+                let fn_name = {
+                    fn f() {}
+                    fn type_name_of<T>(_: T) -> &'static str {
+                        std::any::type_name::<T>()
+                    }
+                    let name = type_name_of(f);
+                    &name[..name.len() - 3]
+                };
+
+                let thread = std::thread::current();
+                let log_tuple = $crate::MsgTuple {
+                    timestamp,
+                    level: log_level,
+                    target: $target.to_string(),
+                    fn_name: fn_name.to_string(),
+                    line: line!(),
+                    column: column!(),
+                    file: file!(),
+                    module_path: module_path!(),
+                    thread_name: thread.name().map(String::from),
+                    thread_id: thread.id(),
+                    msg: format!($( $fmt_args ),*),
+                    fields: $crate::__mt_fields!([] $( $fields )*),
+                };
+
+                instance.log_tuple(log_tuple).ok();
+            }
+        }
+    }};
+    ($log_level:expr, $( $fmt_args:expr ),*) => {
+        $crate::mt_log!(target: module_path!(), $log_level, $( $fmt_args ),*;)
+    };
+    ($log_level:expr, $( $fmt_args:expr ),*; $( $fields:tt )*) => {
+        $crate::mt_log!(target: module_path!(), $log_level, $( $fmt_args ),*; $( $fields )*)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+/// Collects the structured fields of [`mt_log!`] into a `Vec<(&'static str, FieldValue)>`
+macro_rules! __mt_fields {
+    ([ $( $field:expr ),* ]) => {
+        vec![$( $field ),*]
+    };
+    ([ $( $field:expr ),* ] $key:ident = ?$value:expr $( , $( $rest:tt )* )?) => {
+        $crate::__mt_fields!(
+            [ $( $field, )* (stringify!($key), $crate::FieldValue::Debug(format!("{:?}", $value))) ]
+            $( $( $rest )* )?
+        )
+    };
+    ([ $( $field:expr ),* ] $key:ident = $value:expr $( , $( $rest:tt )* )?) => {
+        $crate::__mt_fields!(
+            [ $( $field, )* (stringify!($key), $crate::FieldValue::from($value)) ]
+            $( $( $rest )* )?
+        )
+    };
+}
+
+/// Sets the active stream to the specified [`OutputStream`].
+///
+/// # Examples
+///
+/// Set active stream to `StdOut`.
+/// ```
+/// # #[macro_use] extern crate mt_logger;
+/// # use mt_logger::OutputStream;
+/// # fn main() {
+/// mt_stream!(OutputStream::StdOut);
+/// # }
+/// ```
+///
+/// Set active stream to `Neither`, i.e., disable logging.
+/// ```
+/// # #[macro_use] extern crate mt_logger;
+/// # use mt_logger::OutputStream;
+/// # fn main() {
+/// mt_stream!(OutputStream::Neither);
 /// # }
 /// ```
 #[macro_export]
@@ -455,563 +1356,2682 @@ macro_rules! mt_stream {
     }};
 }
 
-/// Sets the minimum logging level to the specified [`Level`].
+/// Sets the minimum [`Level`] of messages written to the specified [`OutputStream`](s).
+///
+/// Stream levels apply in addition to the levels set by [`mt_level!`], so a message is only written
+/// to a stream if it passes both. `OutputStream::Both` sets the level of both streams, and
+/// `OutputStream::Split` sets the level of both StdOut and StdErr.
 ///
 /// # Examples
 ///
-/// Log all messages at `Debug`-level or higher.
+/// Keep the full history in the logfile, but only show warnings and higher on the console.
 /// ```
 /// # #[macro_use] extern crate mt_logger;
-/// # use mt_logger::Level;
+/// # use mt_logger::{Level, OutputStream};
 /// # fn main() {
-/// mt_level!(Level::Debug);
+/// mt_level!(Level::Trace);
+/// mt_stream_level!(OutputStream::StdOut, Level::Warning);
 /// # }
 /// ```
+#[macro_export]
+macro_rules! mt_stream_level {
+    ($output_stream:expr, $output_level:expr) => {{
+        // Get the global instance and send a command to set the stream's output level
+        $crate::INSTANCE
+            .get()
+            // If None is encountered, the logger has not been initialized, so do nothing
+            .and_then(|instance| {
+                instance
+                    .log_cmd($crate::Command::SetStreamLevel(
+                        $output_stream,
+                        $output_level,
+                    ))
+                    .ok()
+            });
+    }};
+}
+
+/// Sets the exact set of levels written to the specified [`OutputStream`](s) to the specified
+/// [`LevelMask`].
 ///
-/// Log all messages at `Fatal`-level or higher, i.e., only log `Fatal`-level messages.
+/// Like [`mt_stream_level!`], stream masks apply in addition to the levels set by [`mt_level!`].
+///
+/// # Examples
+///
+/// Exclude `Info` messages from the console.
 /// ```
 /// # #[macro_use] extern crate mt_logger;
-/// # use mt_logger::Level;
+/// # use mt_logger::{Level, LevelMask, OutputStream};
 /// # fn main() {
-/// mt_level!(Level::Fatal);
+/// mt_stream_level_mask!(OutputStream::StdOut, LevelMask::all().without(Level::Info));
 /// # }
 /// ```
 #[macro_export]
-macro_rules! mt_level {
-    ($output_level:expr) => {{
-        // Get the global instance and send a command to set the output level
+macro_rules! mt_stream_level_mask {
+    ($output_stream:expr, $output_mask:expr) => {{
+        // Get the global instance and send a command to set the stream's level mask
         $crate::INSTANCE
             .get()
             // If None is encountered, the logger has not been initialized, so do nothing
             .and_then(|instance| {
                 instance
-                    .log_cmd($crate::Command::SetOutputLevel($output_level))
+                    .log_cmd($crate::Command::SetStreamLevelMask(
+                        $output_stream,
+                        $crate::LevelMask::from($output_mask),
+                    ))
                     .ok()
             });
     }};
 }
 
-/// Returns a count of _recorded_ log messages.
-///
-/// NOTE: This may not (and likely _is_ not, at any given time), the same as the
-/// number of times [`mt_log!`] has been called. The message count is only incremented
-/// after the logging thread has successfully written a message to the active
-/// stream(s). Due to the nature of multithreading, this may happen at any time or never.
+/// Sets the exact set of levels written to the specified [`SinkId`] to the specified [`LevelMask`].
 ///
-/// If a count of all successfully sent and recorded messages is required, [`mt_flush!`]
-/// must be called before [`mt_count!`].
+/// Like [`mt_stream_level!`], sink masks apply in addition to the levels set by [`mt_level!`].
+/// [`SinkId::CONSOLE`] and [`SinkId::FILE`] refer to the built-in sinks.
 ///
 /// # Examples
 ///
-/// Get count of recorded messages.
+/// Only write `Error`-level messages and higher to a custom sink.
 /// ```
 /// # #[macro_use] extern crate mt_logger;
-/// # use mt_logger::{Level, OutputStream};
-/// # fn main() {
+/// # use std::io;
+/// # use mt_logger::{Level, LevelMask, MsgTuple, MtLoggerError, OutputStream, Sink};
+/// # struct Alerter;
+/// # impl Sink for Alerter {
+/// #     fn record(&mut self, _: &MsgTuple) -> io::Result<()> { Ok(()) }
+/// # }
+/// # fn main() -> Result<(), MtLoggerError> {
 /// # mt_new!(None, Level::Info, OutputStream::Both);
-/// let msg_count = mt_count!();
+/// let sink_id = mt_add_sink!(Alerter)?;
+/// mt_sink_level_mask!(sink_id, LevelMask::at_or_above(Level::Error));
+/// # Ok(())
 /// # }
 /// ```
 #[macro_export]
-macro_rules! mt_count {
-    () => {{
-        // Get the global instance and retrieve the message count
+macro_rules! mt_sink_level_mask {
+    ($sink_id:expr, $output_mask:expr) => {{
+        // Get the global instance and send a command to set the sink's level mask
         $crate::INSTANCE
             .get()
-            // If None is encountered, the logger has not been initialized, which is an error
-            .and_then(|instance| Some(instance.msg_count()))
-            .unwrap()
+            // If None is encountered, the logger has not been initialized, so do nothing
+            .and_then(|instance| {
+                instance
+                    .log_cmd($crate::Command::SetSinkLevelMask(
+                        $sink_id,
+                        $crate::LevelMask::from($output_mask),
+                    ))
+                    .ok()
+            });
     }};
 }
 
-/// Blocks the calling thread until all messages have been received by the logging thread.
+/// Registers a [`Sink`] with the global instance, returning its [`SinkId`].
 ///
-/// Returns [`Result<(), MtLoggerError>`]
+/// Messages logged after this call are written to the sink in addition to any other sinks, until
+/// it is removed with [`mt_remove_sink!`].
+///
+/// Returns [`Result<SinkId, MtLoggerError>`]
 ///
 /// # Examples
 ///
-/// Flush all sent messages.
+/// Write messages to an additional file.
 /// ```
 /// # #[macro_use] extern crate mt_logger;
-/// # use mt_logger::{Level, MtLoggerError, OutputStream};
-/// # fn main() -> Result<(), MtLoggerError> {
-
-/// # mt_new!(None, Level::Info, OutputStream::Both);/// mt_log!(Level::Debug, "These");
-/// mt_log!(Level::Debug, "messages");
-/// mt_log!(Level::Debug, "may");
-/// mt_log!(Level::Debug, "not");
-/// mt_log!(Level::Debug, "have");
-/// mt_log!(Level::Debug, "been");
-/// mt_log!(Level::Debug, "received");
-/// mt_log!(Level::Debug, "yet.");
-///
-/// mt_flush!()?;
-/// // Now they have!
+/// # use std::fs::File;
+/// # use mt_logger::{FileSink, Level, MtLoggerError, OutputStream};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # mt_new!(None, Level::Info, OutputStream::Both);
+/// let logfile = File::create(std::env::temp_dir().join("mt_logger_extra.log"))?;
+/// let sink_id = mt_add_sink!(FileSink::new(logfile))?;
+/// mt_log!(Level::Info, "Written to both logfiles.");
 ///
-/// Ok(())
+/// mt_remove_sink!(sink_id)?;
+/// # Ok(())
 /// # }
 /// ```
 ///
 /// # Errors
 ///
-/// As this function is effectively a send and blocking receive, it is possible for
-/// either of these calls to fail, and those errors will propagate back to the caller.
-///
 /// See [`MtLoggerError`] for an enumeration of errors that may be returned.
 #[macro_export]
-macro_rules! mt_flush {
-    () => {
+macro_rules! mt_add_sink {
+    ($sink:expr) => {
         $crate::INSTANCE.get().map_or(
             // If None is encountered, the logger has not been initialized, just return an error
             Err($crate::MtLoggerError::LoggerNotInitialized),
-            // If instance is initialized, allow all messages to flush to output
-            |instance| instance.flush(),
+            // If instance is initialized, hand the sink over to the logging thread
+            |instance| instance.add_sink(Box::new($sink)),
         )
     };
 }
 
+/// Closes and removes the [`Sink`] with the specified [`SinkId`] from the global instance.
+///
+/// Removing [`SinkId::CONSOLE`] or [`SinkId::FILE`] removes the corresponding built-in sink, after
+/// which [`mt_stream!`] no longer affects it. Removing an unknown ID does nothing.
+///
+/// Returns [`Result<(), MtLoggerError>`]
+///
+/// # Errors
+///
+/// See [`MtLoggerError`] for an enumeration of errors that may be returned.
+#[macro_export]
+macro_rules! mt_remove_sink {
+    ($sink_id:expr) => {
+        $crate::INSTANCE.get().map_or(
+            // If None is encountered, the logger has not been initialized, just return an error
+            Err($crate::MtLoggerError::LoggerNotInitialized),
+            // If instance is initialized, have the logging thread close the sink
+            |instance| instance.remove_sink($sink_id),
+        )
+    };
+}
 
-///////////////////////////////////////////////////////////////////////////////
-//  Unit Tests
-///////////////////////////////////////////////////////////////////////////////
-
-#[cfg(test)]
-mod tests {
-    use std::error::Error;
-    use std::fs;
-    use std::io::Read;
-    use std::path::PathBuf;
-    use std::sync::Mutex;
-    use std::time;
-
-    use lazy_static::lazy_static;
-
-    use regex::Regex;
-
-    use crate::receiver::{FILE_OUT_FILENAME, STDOUT_FILENAME};
-    use crate::{Level, OutputStream, INSTANCE};
-
-
-    type TestResult = Result<(), Box<dyn Error>>;
-
-
-    lazy_static! {
-        static ref LOGGER_MUTEX: Mutex<()> = Mutex::new(());
-    }
-
-
-    #[derive(Debug, PartialEq)]
-    enum VerfFile {
-        StdOut,
-        FileOut,
-    }
-
-    const LOGFILE_PREFIX: Option<&'static str> = Some("TEST");
-
-    const STDOUT_HDR_REGEX_STR: &str = r"^\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}.\d{9}: \x1b\[(\d{3};\d{3}m)\[(\s*(\w*)\s*)\]\x1b\[0m (.*)\(\) line (\d*):";
-    const STDOUT_COLOR_IDX: usize = 1;
-    const STDOUT_PADDED_LEVEL_IDX: usize = 2;
-    const STDOUT_PADLESS_LEVEL_IDX: usize = 3;
-    const STDOUT_FN_NAME_IDX: usize = 4;
-    const STDOUT_LINE_NUM_IDX: usize = 5;
-
-    const FILE_OUT_HDR_REGEX_STR: &str =
-        r"^\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}.\d{9}: \[(\s*(\w*)\s*)\] (.*)\(\) line (\d*):";
-    const FILE_OUT_PADDED_LEVEL_IDX: usize = 1;
-    const FILE_OUT_PADLESS_LEVEL_IDX: usize = 2;
-    const FILE_OUT_FN_NAME_IDX: usize = 3;
-    const FILE_OUT_LINE_NUM_IDX: usize = 4;
+/// Sets the minimum logging level to the specified [`Level`].
+///
+/// With a leading `target:` argument, sets the minimum level for messages whose target or function
+/// name is, or is nested under, the given target. The most specific matching target takes
+/// precedence, and messages without a matching target use the level set without a target.
+///
+/// # Examples
+///
+/// Log all messages at `Debug`-level or higher.
+/// ```
+/// # #[macro_use] extern crate mt_logger;
+/// # use mt_logger::Level;
+/// # fn main() {
+/// mt_level!(Level::Debug);
+/// # }
+/// ```
+///
+/// Log all messages at `Fatal`-level or higher, i.e., only log `Fatal`-level messages.
+/// ```
+/// # #[macro_use] extern crate mt_logger;
+/// # use mt_logger::Level;
+/// # fn main() {
+/// mt_level!(Level::Fatal);
+/// # }
+/// ```
+///
+/// Trace the network layer, but only log warnings and higher for the database layer.
+/// ```
+/// # #[macro_use] extern crate mt_logger;
+/// # use mt_logger::Level;
+/// # fn main() {
+/// mt_level!(target: "my_app::net", Level::Trace);
+/// mt_level!(target: "my_app::db", Level::Warning);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! mt_level {
+    (target: $target:expr, $output_level:expr) => {{
+        // Get the global instance and send a command to set the target's output level
+        $crate::INSTANCE
+            .get()
+            // If None is encountered, the logger has not been initialized, so do nothing
+            .and_then(|instance| {
+                instance
+                    .log_cmd($crate::Command::SetTargetLevel(
+                        $target.to_string(),
+                        $output_level,
+                    ))
+                    .ok()
+            });
+    }};
+    ($output_level:expr) => {{
+        // Get the global instance and send a command to set the output level
+        $crate::INSTANCE
+            .get()
+            // If None is encountered, the logger has not been initialized, so do nothing
+            .and_then(|instance| {
+                instance
+                    .log_cmd($crate::Command::SetOutputLevel($output_level))
+                    .ok()
+            });
+    }};
+}
+
+/// Applies filter directives to the active logging levels, e.g., `info,my_app::net=trace`.
+///
+/// See [`MtLoggerBuilder::directives`] for the format. A bare level is applied as with
+/// [`mt_level!`], and a `target=level` directive as with `mt_level!(target: ...)`.
+///
+/// Returns [`Result<(), MtLoggerError>`]
+///
+/// # Examples
+///
+/// Apply directives read from a configuration file at runtime.
+/// ```
+/// # #[macro_use] extern crate mt_logger;
+/// # use mt_logger::{Level, MtLoggerError, OutputStream};
+/// # fn main() -> Result<(), MtLoggerError> {
+/// # mt_new!(None, Level::Info, OutputStream::Both);
+/// mt_directives!("warning,my_app::net=trace,my_app::db=error")?;
+///
+/// match mt_directives!("my_app::net=verbose") {
+///     Err(MtLoggerError::InvalidDirective(directive)) => assert_eq!("my_app::net=verbose", directive),
+///     _ => panic!("Invalid directive should be reported"),
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Returns [`MtLoggerError::InvalidDirective`] if any directive cannot be parsed, in which case no
+/// directives are applied.
+///
+/// See [`MtLoggerError`] for an enumeration of errors that may be returned.
+#[macro_export]
+macro_rules! mt_directives {
+    ($directives:expr) => {
+        $crate::INSTANCE.get().map_or(
+            // If None is encountered, the logger has not been initialized, just return an error
+            Err($crate::MtLoggerError::LoggerNotInitialized),
+            // If instance is initialized, parse and apply the directives
+            |instance| instance.apply_directives($directives),
+        )
+    };
+}
+
+/// Sets the exact set of levels to be logged to the specified [`LevelMask`].
+///
+/// Unlike [`mt_level!`], which logs every level at or above a minimum, only the levels in the mask
+/// are logged. A leading `target:` argument applies the mask to a target as in [`mt_level!`].
+///
+/// # Examples
+///
+/// Log only `Trace` and `Error` messages.
+/// ```
+/// # #[macro_use] extern crate mt_logger;
+/// # use mt_logger::Level;
+/// # fn main() {
+/// mt_level_mask!(Level::Trace | Level::Error);
+/// # }
+/// ```
+///
+/// Log everything from the network layer except `Info` messages.
+/// ```
+/// # #[macro_use] extern crate mt_logger;
+/// # use mt_logger::LevelMask;
+/// # use mt_logger::Level;
+/// # fn main() {
+/// mt_level_mask!(target: "my_app::net", LevelMask::all().without(Level::Info));
+/// # }
+/// ```
+#[macro_export]
+macro_rules! mt_level_mask {
+    (target: $target:expr, $output_mask:expr) => {{
+        // Get the global instance and send a command to set the target's level mask
+        $crate::INSTANCE
+            .get()
+            // If None is encountered, the logger has not been initialized, so do nothing
+            .and_then(|instance| {
+                instance
+                    .log_cmd($crate::Command::SetTargetLevelMask(
+                        $target.to_string(),
+                        $crate::LevelMask::from($output_mask),
+                    ))
+                    .ok()
+            });
+    }};
+    ($output_mask:expr) => {{
+        // Get the global instance and send a command to set the level mask
+        $crate::INSTANCE
+            .get()
+            // If None is encountered, the logger has not been initialized, so do nothing
+            .and_then(|instance| {
+                instance
+                    .log_cmd($crate::Command::SetLevelMask($crate::LevelMask::from(
+                        $output_mask,
+                    )))
+                    .ok()
+            });
+    }};
+}
+
+/// Returns a count of _recorded_ log messages.
+///
+/// NOTE: This may not (and likely _is_ not, at any given time), the same as the
+/// number of times [`mt_log!`] has been called. The message count is only incremented
+/// after the logging thread has successfully written a message to the active
+/// stream(s). Due to the nature of multithreading, this may happen at any time or never.
+///
+/// If a count of all successfully sent and recorded messages is required, [`mt_flush!`]
+/// must be called before [`mt_count!`].
+///
+/// # Examples
+///
+/// Get count of recorded messages.
+/// ```
+/// # #[macro_use] extern crate mt_logger;
+/// # use mt_logger::{Level, OutputStream};
+/// # fn main() {
+/// # mt_new!(None, Level::Info, OutputStream::Both);
+/// let msg_count = mt_count!();
+/// # }
+/// ```
+#[macro_export]
+macro_rules! mt_count {
+    () => {{
+        // Get the global instance and retrieve the message count
+        $crate::INSTANCE
+            .get()
+            // If None is encountered, the logger has not been initialized, which is an error
+            .and_then(|instance| Some(instance.msg_count()))
+            .unwrap()
+    }};
+}
+
+/// Blocks the calling thread until all messages have been received by the logging thread.
+///
+/// Returns [`Result<(), MtLoggerError>`]
+///
+/// # Examples
+///
+/// Flush all sent messages.
+/// ```
+/// # #[macro_use] extern crate mt_logger;
+/// # use mt_logger::{Level, MtLoggerError, OutputStream};
+/// # fn main() -> Result<(), MtLoggerError> {
+/// # mt_new!(None, Level::Info, OutputStream::Both);
+/// mt_log!(Level::Debug, "These");
+/// mt_log!(Level::Debug, "messages");
+/// mt_log!(Level::Debug, "may");
+/// mt_log!(Level::Debug, "not");
+/// mt_log!(Level::Debug, "have");
+/// mt_log!(Level::Debug, "been");
+/// mt_log!(Level::Debug, "received");
+/// mt_log!(Level::Debug, "yet.");
+///
+/// mt_flush!()?;
+/// // Now they have!
+///
+/// Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// As this function is effectively a send and blocking receive, it is possible for
+/// either of these calls to fail, and those errors will propagate back to the caller.
+///
+/// See [`MtLoggerError`] for an enumeration of errors that may be returned.
+#[macro_export]
+macro_rules! mt_flush {
+    () => {
+        $crate::INSTANCE.get().map_or(
+            // If None is encountered, the logger has not been initialized, just return an error
+            Err($crate::MtLoggerError::LoggerNotInitialized),
+            // If instance is initialized, allow all messages to flush to output
+            |instance| instance.flush(),
+        )
+    };
+}
+
+/// Reopens the files written by every sink of the global instance, e.g., the logfile.
+///
+/// Call this after an external tool such as logrotate has renamed the logfile, so that messages
+/// are written to a new file at the original path rather than the renamed one. Messages logged
+/// before this call are still written to the renamed file. See also
+/// [`MtLoggerBuilder::reopen_on_signal`].
+///
+/// Returns [`Result<(), MtLoggerError>`]
+///
+/// # Examples
+///
+/// ```
+/// # use mt_logger::*;
+/// # fn main() -> Result<(), MtLoggerError> {
+/// # mt_new!(None, Level::Info, OutputStream::File);
+/// mt_log!(Level::Info, "Written to the logfile before it is rotated");
+///
+/// // The logfile has been renamed by logrotate
+/// mt_reopen_files!()?;
+///
+/// mt_log!(Level::Info, "Written to a new logfile at the original path");
+///
+/// Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// See [`MtLoggerError`] for an enumeration of errors that may be returned.
+#[macro_export]
+macro_rules! mt_reopen_files {
+    () => {
+        $crate::INSTANCE.get().map_or(
+            // If None is encountered, the logger has not been initialized, just return an error
+            Err($crate::MtLoggerError::LoggerNotInitialized),
+            // If instance is initialized, have the logging thread reopen its files
+            |instance| instance.reopen_files(),
+        )
+    };
+}
+
+/// Processes all queued messages, closes the logfile, and stops the logging thread.
+///
+/// Blocks the calling thread until the logging thread has acknowledged the shutdown and exited.
+/// Any messages sent after this point are discarded.
+///
+/// Returns [`Result<(), MtLoggerError>`]
+///
+/// # Examples
+///
+/// Shut down the logger at the end of a program.
+/// ```
+/// # #[macro_use] extern crate mt_logger;
+/// # use mt_logger::{Level, MtLoggerError, OutputStream};
+/// # fn main() -> Result<(), MtLoggerError> {
+/// # mt_new!(None, Level::Info, OutputStream::Both);
+/// mt_log!(Level::Info, "Exiting...");
+///
+/// mt_shutdown!()?;
+/// // The "Exiting..." message is guaranteed to have been recorded
+///
+/// Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Like [`mt_flush!`], this is effectively a send and blocking receive, and errors from either will
+/// propagate back to the caller. [`MtLoggerError::ReceiverDied`] is returned if the logging thread
+/// could not be joined.
+///
+/// See [`MtLoggerError`] for an enumeration of errors that may be returned.
+#[macro_export]
+macro_rules! mt_shutdown {
+    () => {
+        $crate::INSTANCE.get().map_or(
+            // If None is encountered, the logger has not been initialized, just return an error
+            Err($crate::MtLoggerError::LoggerNotInitialized),
+            // If instance is initialized, drain the queue and join the logging thread
+            |instance| instance.shutdown(),
+        )
+    };
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Unit Tests
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+    use std::fs;
+    use std::io::Read;
+    use std::panic::Location;
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::AtomicU64;
+    use std::sync::mpsc::SendError;
+    use std::sync::{Arc, Mutex, MutexGuard, Weak};
+    use std::thread;
+    use std::time;
+
+    use chrono::{Datelike, Local, TimeZone, Utc};
+
+    use lazy_static::lazy_static;
+
+    use regex::Regex;
+
+    use crate::channel;
+    use crate::filter::{self, Directives};
+    use crate::receiver::{FILE_OUT_FILENAME, STDERR_FILENAME, STDOUT_FILENAME};
+    #[cfg(target_os = "linux")]
+    use crate::ReopenSignal;
+    use crate::{
+        Command, DurabilityPolicy, FieldValue, Level, LevelMask, MsgTuple, MtLogger,
+        MtLoggerBuilder, MtLoggerError, MtLoggerGuard, OutputStream, OverflowPolicy,
+        RotationInterval, RotationStyle, Sink, SinkId, INSTANCE,
+    };
+
+
+    type TestResult = Result<(), Box<dyn Error>>;
+
+
+    lazy_static! {
+        static ref LOGGER_MUTEX: Mutex<()> = Mutex::new(());
+    }
+
+
+    #[derive(Debug, PartialEq)]
+    enum VerfFile {
+        StdOut,
+        FileOut,
+    }
+
+    const LOGFILE_PREFIX: Option<&'static str> = Some("TEST");
+
+    const STDOUT_HDR_REGEX_STR: &str = r"^\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}.\d{9}: \x1b\[(\d{3};\d{3}m)\[(\s*(\w*)\s*)\]\x1b\[0m (.*)\(\) line (\d*):";
+    const STDOUT_COLOR_IDX: usize = 1;
+    const STDOUT_PADDED_LEVEL_IDX: usize = 2;
+    const STDOUT_PADLESS_LEVEL_IDX: usize = 3;
+    const STDOUT_FN_NAME_IDX: usize = 4;
+    const STDOUT_LINE_NUM_IDX: usize = 5;
+
+    const FILE_OUT_HDR_REGEX_STR: &str =
+        r"^\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}.\d{9}: \[(\s*(\w*)\s*)\] (.*)\(\) line (\d*):";
+    const FILE_OUT_PADDED_LEVEL_IDX: usize = 1;
+    const FILE_OUT_PADLESS_LEVEL_IDX: usize = 2;
+    const FILE_OUT_FN_NAME_IDX: usize = 3;
+    const FILE_OUT_LINE_NUM_IDX: usize = 4;
+
+
+    // Reset verification files, if they exist
+    fn reset_verf_files() -> TestResult {
+        let path_buf = PathBuf::from(STDOUT_FILENAME);
+        if path_buf.as_path().exists() {
+            fs::write(STDOUT_FILENAME, "")?;
+        }
+
+        let path_buf = PathBuf::from(FILE_OUT_FILENAME);
+        if path_buf.as_path().exists() {
+            fs::write(FILE_OUT_FILENAME, "")?;
+        }
+
+        let path_buf = PathBuf::from(STDERR_FILENAME);
+        if path_buf.as_path().exists() {
+            fs::write(STDERR_FILENAME, "")?;
+        }
+
+        Ok(())
+    }
+
+    // Locks the logger mutex, as new receivers will reset the verification files, and returns an
+    // empty log directory for the given test within the system's temporary directory
+    fn lock_log_dir(test_name: &str) -> Result<(MutexGuard<'static, ()>, PathBuf), Box<dyn Error>> {
+        let mutex = LOGGER_MUTEX.lock()?;
+
+        let log_dir = std::env::temp_dir().join(format!("mt_logger_{}", test_name));
+        if log_dir.exists() {
+            fs::remove_dir_all(&log_dir)?;
+        }
+        fs::create_dir_all(&log_dir)?;
+
+        Ok((mutex, log_dir))
+    }
+
+    // Starts building a standalone logger writing only to `{prefix}.log` in the given directory
+    fn file_logger_builder(logfile_prefix: &str, log_dir: &Path) -> MtLoggerBuilder {
+        MtLoggerBuilder::new(logfile_prefix)
+            .output_stream(OutputStream::File)
+            .log_dir(log_dir)
+            .file_name_template("{prefix}.log")
+    }
+
+    // Logs a message through a standalone logger, recording the calling line
+    #[track_caller]
+    fn log_line(
+        logger: &MtLogger,
+        level: Level,
+        msg: impl Into<String>,
+    ) -> Result<(), SendError<Command>> {
+        logger.log_msg(
+            crate::mt_now(),
+            level,
+            "mt_logger::tests::log_line".to_string(),
+            Location::caller().line(),
+            msg.into(),
+        )
+    }
+
+    // Creates a message logged from the calling line of the current thread, with the default
+    // target
+    #[track_caller]
+    fn msg_tuple(level: Level, msg: impl Into<String>) -> MsgTuple {
+        let caller = Location::caller();
+        let thread = thread::current();
+        MsgTuple {
+            timestamp: crate::mt_now(),
+            level,
+            target: module_path!().to_string(),
+            fn_name: "mt_logger::tests::msg_tuple".to_string(),
+            line: caller.line(),
+            column: caller.column(),
+            file: caller.file(),
+            module_path: module_path!(),
+            thread_name: thread.name().map(String::from),
+            thread_id: thread.id(),
+            msg: msg.into(),
+            fields: Vec::new(),
+        }
+    }
+
+    // Returns the trimmed lines of the given output starting with any of the given prefixes,
+    // i.e., the content of the messages recorded in it
+    fn recorded_msgs(output: &str, prefixes: &[&str]) -> Vec<String> {
+        output
+            .lines()
+            .map(str::trim)
+            .filter(|line| prefixes.iter().any(|prefix| line.starts_with(prefix)))
+            .map(String::from)
+            .collect()
+    }
+
+    // Creates an empty file in the given directory, last modified the given number of hours ago
+    fn create_aged_file(dir: &Path, file_name: &str, age_hours: u64) -> TestResult {
+        let file = fs::File::create(dir.join(file_name))?;
+        file.set_modified(time::SystemTime::now() - time::Duration::from_secs(age_hours * 3600))?;
+
+        Ok(())
+    }
+
+    // Returns the sorted names of the files in the given directory
+    fn list_files(dir: &Path) -> Result<Vec<String>, Box<dyn Error>> {
+        let mut file_names = fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.file_name().to_string_lossy().into_owned()))
+            .collect::<Result<Vec<_>, _>>()?;
+        file_names.sort();
+
+        Ok(file_names)
+    }
+
+    fn format_verf_helper(
+        verf_type: VerfFile,
+        verf_string: String,
+        first_line_num: u32,
+    ) -> TestResult {
+        // Set up the verification items
+        const FN_NAME: &str = "mt_logger::tests::format_verification";
+        const VERF_MATRIX: [[&str; 3]; 6] = [
+            ["TRACE", "030;105m", "  TRACE  "],
+            ["DEBUG", "030;106m", "  DEBUG  "],
+            ["INFO", "030;107m", "  INFO   "],
+            ["WARNING", "030;103m", " WARNING "],
+            ["ERROR", "030;101m", "  ERROR  "],
+            ["FATAL", "031;040m", "  FATAL  "],
+        ];
+        const LEVEL_VERF_IDX: usize = 0;
+        const COLOR_VERF_IDX: usize = 1;
+        const PADDED_LEVEL_VERF_IDX: usize = 2;
+
+        // Set up output-specific parameters
+        let padded_level_hdr_capture_idx;
+        let fn_name_hdr_capture_idx;
+        let line_num_hdr_capture_idx;
+        let header_regex;
+        match verf_type {
+            VerfFile::StdOut => {
+                padded_level_hdr_capture_idx = STDOUT_PADDED_LEVEL_IDX;
+                fn_name_hdr_capture_idx = STDOUT_FN_NAME_IDX;
+                line_num_hdr_capture_idx = STDOUT_LINE_NUM_IDX;
+                header_regex = Regex::new(STDOUT_HDR_REGEX_STR)?;
+            }
+            VerfFile::FileOut => {
+                padded_level_hdr_capture_idx = FILE_OUT_PADDED_LEVEL_IDX;
+                fn_name_hdr_capture_idx = FILE_OUT_FN_NAME_IDX;
+                line_num_hdr_capture_idx = FILE_OUT_LINE_NUM_IDX;
+                header_regex = Regex::new(FILE_OUT_HDR_REGEX_STR)?;
+            }
+        }
+        let level_content_capture_idx = 1;
+
+        // Create regex for message content
+        let content_regex = Regex::new(r"^   This is an? (\w*) message.")?;
+
+        // Read verf string into iterator
+        let mut verf_lines: Vec<&str> = verf_string.split('\n').collect();
+        let mut verf_line_iter = verf_lines.iter_mut();
+
+        // Iterate over lines, verifying along the way
+        let mut i = 0;
+        while let Some(header_line) = verf_line_iter.next().filter(|v| !v.is_empty()) {
+            // Match regex against header line, and capture groups
+            let header_captures = header_regex.captures(header_line).unwrap_or_else(|| {
+                panic!(
+                    "{:?}: Header line {} '{}' did not match Regex:\n   {}",
+                    verf_type,
+                    i,
+                    header_line,
+                    header_regex.as_str()
+                )
+            });
+
+            // Verify capture groups
+            if verf_type == VerfFile::StdOut
+                && &header_captures[STDOUT_COLOR_IDX] != VERF_MATRIX[i][COLOR_VERF_IDX]
+            {
+                panic!(
+                    "{:?}: Wrong color '{}' on line '{}', should be '{}'",
+                    verf_type,
+                    &header_captures[STDOUT_COLOR_IDX],
+                    header_line,
+                    VERF_MATRIX[i][COLOR_VERF_IDX]
+                );
+            }
+            if &header_captures[padded_level_hdr_capture_idx]
+                != VERF_MATRIX[i][PADDED_LEVEL_VERF_IDX]
+            {
+                panic!(
+                    "{:?}: Wrong padded level '{}' on line '{}', should be '{}'",
+                    verf_type,
+                    &header_captures[padded_level_hdr_capture_idx],
+                    header_line,
+                    VERF_MATRIX[i][PADDED_LEVEL_VERF_IDX]
+                );
+            }
+            if &header_captures[fn_name_hdr_capture_idx] != FN_NAME {
+                panic!(
+                    "{:?}: Wrong function name '{}' on line '{}', should be '{}'",
+                    verf_type, &header_captures[fn_name_hdr_capture_idx], header_line, FN_NAME
+                );
+            }
+            if header_captures[line_num_hdr_capture_idx].parse::<u32>()?
+                != first_line_num + i as u32
+            {
+                panic!(
+                    "{:?}: Wrong line number '{}' on line '{}', should be '{}'",
+                    verf_type,
+                    &header_captures[line_num_hdr_capture_idx],
+                    header_line,
+                    first_line_num + i as u32
+                );
+            }
+
+            // Verify content line
+            let content_line = verf_line_iter
+                .next()
+                .unwrap_or_else(|| panic!("Missing content line after header '{}'", header_line));
+            let content_captures = content_regex.captures(content_line).unwrap_or_else(|| {
+                panic!(
+                    "{:?}: Content line {} '{}' did not match content Regex:\n   {}",
+                    verf_type,
+                    i,
+                    content_line,
+                    content_regex.as_str()
+                )
+            });
+
+            if &content_captures[level_content_capture_idx] != VERF_MATRIX[i][LEVEL_VERF_IDX] {
+                panic!(
+                    "{:?}: Wrong level '{}' in content line '{}', should be '{}'",
+                    verf_type,
+                    &content_captures[level_content_capture_idx],
+                    content_line,
+                    VERF_MATRIX[i][LEVEL_VERF_IDX]
+                )
+            }
+
+            i += 1;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn format_verification() -> TestResult {
+        // Lock logger mutex and hold it until we're done processing messages
+        let mutex = LOGGER_MUTEX.lock()?;
+
+        // Clean verification files before test
+        reset_verf_files()?;
+
+        // Create or update logger instance such that all messages are logged to Both outputs
+        if INSTANCE.get().is_none() {
+            mt_new!(LOGFILE_PREFIX, Level::Trace, OutputStream::Both);
+        } else {
+            mt_level!(Level::Trace);
+            mt_stream!(OutputStream::Both);
+        }
+
+        let first_line_num = line!() + 1;
+        mt_log!(Level::Trace, "This is a TRACE message.");
+        mt_log!(Level::Debug, "This is a DEBUG message.");
+        mt_log!(Level::Info, "This is an INFO message.");
+        mt_log!(Level::Warning, "This is a WARNING message.");
+        mt_log!(Level::Error, "This is an ERROR message.");
+        mt_log!(Level::Fatal, "This is a FATAL message.");
+
+        // Flush the messages to their output
+        println!("Flushing all messages to their output...");
+        let start_time = time::Instant::now();
+        mt_flush!()?;
+        println!("Done flushing after {}ms", start_time.elapsed().as_millis());
+
+        // Capture the files in memory before releasing the mutex
+        let mut verf_file_stdout = fs::OpenOptions::new().read(true).open(STDOUT_FILENAME)?;
+        let mut verf_string_stdout = String::new();
+        verf_file_stdout.read_to_string(&mut verf_string_stdout)?;
+        let mut verf_file_file_out = fs::OpenOptions::new().read(true).open(FILE_OUT_FILENAME)?;
+        let mut verf_string_file_out = String::new();
+        verf_file_file_out.read_to_string(&mut verf_string_file_out)?;
+
+        // Unlock the mutex
+        std::mem::drop(mutex);
+
+        // Verify that the verification files contain well-formatted messages
+        format_verf_helper(VerfFile::StdOut, verf_string_stdout, first_line_num)?;
+        format_verf_helper(VerfFile::FileOut, verf_string_file_out, first_line_num)?;
+
+        Ok(())
+    }
+
+    fn outputstream_verf_helper(verf_type: VerfFile, verf_string: String) -> TestResult {
+        // Set up the verification items
+        const VERF_MATRIX: [[[&str; 2]; 4]; 2] = [
+            [
+                ["TRACE", "BOTH"],
+                ["FATAL", "BOTH"],
+                ["TRACE", "STDOUT"],
+                ["FATAL", "STDOUT"],
+            ],
+            [
+                ["TRACE", "BOTH"],
+                ["FATAL", "BOTH"],
+                ["TRACE", "FILEOUT"],
+                ["FATAL", "FILEOUT"],
+            ],
+        ];
+        const STDOUT_TYPE_IDX: usize = 0;
+        const FILE_OUT_TYPE_IDX: usize = 1;
+        const LEVEL_VERF_IDX: usize = 0;
+        const OUTPUT_TYPE_VERF_IDX: usize = 1;
+
+        // Set up output-specific parameters
+        let verf_type_idx;
+        let padless_level_hdr_capture_idx;
+        let header_regex;
+        match verf_type {
+            VerfFile::StdOut => {
+                verf_type_idx = STDOUT_TYPE_IDX;
+                padless_level_hdr_capture_idx = STDOUT_PADLESS_LEVEL_IDX;
+                header_regex = Regex::new(STDOUT_HDR_REGEX_STR)?;
+            }
+            VerfFile::FileOut => {
+                verf_type_idx = FILE_OUT_TYPE_IDX;
+                padless_level_hdr_capture_idx = FILE_OUT_PADLESS_LEVEL_IDX;
+                header_regex = Regex::new(FILE_OUT_HDR_REGEX_STR)?;
+            }
+        }
+        let output_type_capture_idx = 1;
+
+        // Create regex for message content
+        let content_regex = Regex::new(r"^\s*This message appears in (\w*).")?;
+
+        // Read verf string into iterator
+        let mut verf_lines: Vec<&str> = verf_string.split('\n').collect();
+        let mut verf_line_iter = verf_lines.iter_mut();
+
+        // Verify that the verification files contain the correct filter level and content lines
+        let mut i = 0;
+        while let Some(header_line) = verf_line_iter.next().filter(|v| !v.is_empty()) {
+            // Verify header contains the correct log level
+            let header_captures = header_regex.captures(header_line).unwrap_or_else(|| {
+                panic!(
+                    "{:?}: Header line {} '{}' did not match Regex:\n   {}",
+                    verf_type,
+                    i,
+                    header_line,
+                    header_regex.as_str()
+                )
+            });
+            if &header_captures[padless_level_hdr_capture_idx]
+                != VERF_MATRIX[verf_type_idx][i][LEVEL_VERF_IDX]
+            {
+                panic!(
+                    "{:?}: Wrong level '{}' on line '{}', should be '{}'",
+                    verf_type,
+                    &header_captures[padless_level_hdr_capture_idx],
+                    header_line,
+                    VERF_MATRIX[verf_type_idx][i][LEVEL_VERF_IDX]
+                );
+            }
+
+            // Verify content contains the correct output type
+            let content_line = verf_line_iter
+                .next()
+                .unwrap_or_else(|| panic!("Missing content line after header '{}'", header_line));
+            let content_captures = content_regex.captures(content_line).unwrap_or_else(|| {
+                panic!(
+                    "{:?}: Content line {} '{}' did not match content Regex:\n   {}",
+                    verf_type,
+                    i,
+                    content_line,
+                    content_regex.as_str()
+                )
+            });
+
+            if &content_captures[output_type_capture_idx]
+                != VERF_MATRIX[verf_type_idx][i][OUTPUT_TYPE_VERF_IDX]
+            {
+                panic!(
+                    "{:?}: Wrong output type '{}' on line '{}', should be '{}'",
+                    verf_type,
+                    &content_captures[output_type_capture_idx],
+                    content_line,
+                    VERF_MATRIX[verf_type_idx][i][OUTPUT_TYPE_VERF_IDX]
+                )
+            }
+
+            i += 1;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn outputstream_verification() -> TestResult {
+        // Lock logger mutex and hold it until we're done processing messages
+        let mutex = LOGGER_MUTEX.lock()?;
+
+        // Clean verification files before test
+        reset_verf_files()?;
+
+        // Create or update logger instance such that all messages are logged to Both outputs
+        if INSTANCE.get().is_none() {
+            mt_new!(LOGFILE_PREFIX, Level::Trace, OutputStream::Both);
+        } else {
+            mt_level!(Level::Trace);
+            mt_stream!(OutputStream::Both);
+        }
+
+        mt_log!(Level::Trace, "This message appears in BOTH.");
+        mt_log!(Level::Fatal, "This message appears in BOTH.");
+
+        // Log messages to STDOUT only
+        mt_stream!(OutputStream::StdOut);
+        mt_log!(Level::Trace, "This message appears in STDOUT.");
+        mt_log!(Level::Fatal, "This message appears in STDOUT.");
+
+        // Log messages to FILE only
+        mt_stream!(OutputStream::File);
+        mt_log!(Level::Trace, "This message appears in FILEOUT.");
+        mt_log!(Level::Fatal, "This message appears in FILEOUT.");
+
+        // Log messages to NEITHER output
+        mt_stream!(OutputStream::Neither);
+        mt_log!(Level::Trace, "This message appears in NEITHER.");
+        mt_log!(Level::Fatal, "This message appears in NEITHER.");
+
+        // Flush the messages to their output
+        println!("Flushing all messages to their output...");
+        let start_time = time::Instant::now();
+        mt_flush!()?;
+        println!("Done flushing after {}ms", start_time.elapsed().as_millis());
+
+        // Capture the files in memory before releasing the mutex
+        let mut verf_file_stdout = fs::OpenOptions::new().read(true).open(STDOUT_FILENAME)?;
+        let mut verf_string_stdout = String::new();
+        verf_file_stdout.read_to_string(&mut verf_string_stdout)?;
+        let mut verf_file_file_out = fs::OpenOptions::new().read(true).open(FILE_OUT_FILENAME)?;
+        let mut verf_string_file_out = String::new();
+        verf_file_file_out.read_to_string(&mut verf_string_file_out)?;
+
+        // Unlock the mutex
+        std::mem::drop(mutex);
+
+        // Verify that the verification files contain only the correct messages
+        outputstream_verf_helper(VerfFile::StdOut, verf_string_stdout)?;
+        outputstream_verf_helper(VerfFile::FileOut, verf_string_file_out)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn fields_test() -> TestResult {
+        // Lock logger mutex and hold it until we're done processing messages
+        let mutex = LOGGER_MUTEX.lock()?;
+
+        // Clean verification files before test
+        reset_verf_files()?;
+
+        // Create or update logger instance such that all messages are logged to Both outputs
+        if INSTANCE.get().is_none() {
+            mt_new!(LOGFILE_PREFIX, Level::Info, OutputStream::Both);
+        } else {
+            mt_level!(Level::Info);
+            mt_stream!(OutputStream::Both);
+        }
+
+        let user_name = String::from("jane doe");
+        let path = PathBuf::from("/index.html");
+        mt_log!(Level::Info, "request done"; user_id = 42, ok = true, latency_ms = 3.2,);
+        mt_log!(Level::Info, "request {}", "failed"; user = user_name, path = ?path, code = 404u16);
+        mt_flush!()?;
+
+        // Capture the files in memory before releasing the mutex
+        let verf_string_stdout = fs::read_to_string(STDOUT_FILENAME)?;
+        let verf_string_file_out = fs::read_to_string(FILE_OUT_FILENAME)?;
+        std::mem::drop(mutex);
+
+        // Verify that fields are rendered after the message content in both outputs
+        for verf_string in [verf_string_stdout, verf_string_file_out].iter() {
+            assert!(verf_string.contains("request done user_id=42 ok=true latency_ms=3.2\n"));
+            assert!(verf_string.contains(
+                "request failed user=\"jane doe\" path=\"\\\"/index.html\\\"\" code=404\n"
+            ));
+        }
+
+        // Verify that fields are carried as typed values
+        assert_eq!(FieldValue::Int(-3), FieldValue::from(-3i8));
+        assert_eq!(FieldValue::UInt(7), FieldValue::from(7usize));
+        assert_eq!(FieldValue::Float(0.5), FieldValue::from(0.5f32));
+        assert_eq!(FieldValue::Bool(false), FieldValue::from(false));
+        assert_eq!(FieldValue::Str("id".to_string()), FieldValue::from("id"));
+        assert_eq!(
+            vec![
+                ("a", FieldValue::Int(1)),
+                ("b", FieldValue::Debug("Some(2)".to_string()))
+            ],
+            crate::__mt_fields!([] a = 1, b = ?Some(2))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn flush_test() -> TestResult {
+        // Lock logger mutex and hold it for the remainder of this test
+        let _mutex = LOGGER_MUTEX.lock()?;
+
+        // Clean verification files before test
+        reset_verf_files()?;
+
+        // Set up the logger instance
+        if INSTANCE.get().is_none() {
+            mt_new!(LOGFILE_PREFIX, Level::Info, OutputStream::StdOut);
+        } else {
+            mt_level!(Level::Info);
+            mt_stream!(OutputStream::StdOut);
+        }
+
+        // Capture the initial count
+        let initial_msg_count = mt_count!();
+        eprintln!("Initial message count: {}", initial_msg_count);
+
+        // Send some messages
+        eprintln!("Sending messages...");
+        let sent_msg_count = 5;
+        for i in 0..sent_msg_count {
+            mt_log!(Level::Info, "Message #{}", i);
+        }
+
+        // Send a flush command
+        mt_flush!()?;
+
+        // Verify that all sent messages were processed after flushing
+        eprintln!("Messages processed: {}", mt_count!());
+        assert_eq!(initial_msg_count + sent_msg_count, mt_count!());
+
+        Ok(())
+    }
+
+    #[test]
+    fn guard_test() -> TestResult {
+        // Lock logger mutex and hold it for the remainder of this test
+        let _mutex = LOGGER_MUTEX.lock()?;
+
+        // Set up the logger instance
+        if INSTANCE.get().is_none() {
+            mt_new!(LOGFILE_PREFIX, Level::Info, OutputStream::StdOut);
+        } else {
+            mt_level!(Level::Info);
+            mt_stream!(OutputStream::StdOut);
+        }
+        let initial_msg_count = mt_count!();
+
+        // Send some messages from within the guard's scope
+        let sent_msg_count = 5;
+        {
+            let _guard = MtLoggerGuard::new();
+            for i in 0..sent_msg_count {
+                mt_log!(Level::Info, "Guarded message #{}", i);
+            }
+        }
+
+        // Verify that all sent messages were processed once the guard was dropped
+        assert_eq!(initial_msg_count + sent_msg_count, mt_count!());
+
+        Ok(())
+    }
+
+    #[test]
+    fn shutdown_test() -> TestResult {
+        // Lock logger mutex, as the new receiver will reset the verification files
+        let _mutex = LOGGER_MUTEX.lock()?;
+
+        // Use a standalone logger so the global instance stays usable for other tests
+        let logger = MtLogger::new("TEST_SHUTDOWN", Level::Info, OutputStream::File);
+
+        // Send some messages
+        let sent_msg_count = 5;
+        for i in 0..sent_msg_count {
+            log_line(&logger, Level::Info, format!("Message #{}", i))?;
+        }
+
+        // Shut down, which must record every queued message before returning
+        logger.shutdown()?;
+        assert_eq!(sent_msg_count, logger.msg_count());
+
+        // Further messages are rejected, and repeated shutdowns are harmless
+        assert!(log_line(&logger, Level::Info, "Too late").is_err());
+        logger.shutdown()?;
+
+        Ok(())
+    }
+
+    // Sink holding up the logging thread until its gate is dropped, recording the content of each
+    // message
+    struct GatedSink {
+        gate: std::sync::mpsc::Receiver<()>,
+        records: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Sink for GatedSink {
+        fn record(&mut self, log_tuple: &MsgTuple) -> std::io::Result<()> {
+            let _ = self.gate.recv();
+            self.records.lock().unwrap().push(log_tuple.msg.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn shutdown_drain_test() -> TestResult {
+        // Lock logger mutex, as the new receiver will reset the verification files
+        let _mutex = LOGGER_MUTEX.lock()?;
+
+        let (gate_tx, gate_rx) = std::sync::mpsc::channel();
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let logger = MtLoggerBuilder::new("TEST_SHUTDOWN")
+            .output_stream(OutputStream::Neither)
+            .sink(
+                SinkId::unique(),
+                GatedSink {
+                    gate: gate_rx,
+                    records: Arc::clone(&records),
+                },
+            )
+            .build();
+
+        // Hold up the logging thread, so that everything below is queued behind the shutdown
+        log_line(&logger, Level::Info, "Recorded before the shutdown")?;
+        let (shutdown_ack_tx, shutdown_ack_rx) = std::sync::mpsc::channel();
+        logger.log_cmd(Command::Shutdown(shutdown_ack_tx))?;
+        logger.log_cmd(Command::SetOutputLevel(Level::Trace))?;
+        log_line(&logger, Level::Trace, "Recorded with the updated level")?;
+        let (flush_ack_tx, flush_ack_rx) = std::sync::mpsc::channel();
+        logger.log_cmd(Command::Flush(flush_ack_tx))?;
+        drop(gate_tx);
+
+        // Every queued command is handled, and every ACK arrives
+        shutdown_ack_rx.recv()?;
+        flush_ack_rx.recv()?;
+        assert_eq!(
+            vec![
+                "Recorded before the shutdown".to_string(),
+                "Recorded with the updated level".to_string(),
+            ],
+            *records.lock().unwrap()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn builder_test() -> TestResult {
+        let (_mutex, log_dir) = lock_log_dir("builder_test")?;
+
+        // Build a standalone logger writing uncolored output to a fixed file in a custom directory
+        let logger = file_logger_builder("TEST_BUILDER", &log_dir)
+            .output_level(Level::Debug)
+            .channel_capacity(1)
+            .timestamp_format("%H:%M:%S")
+            .colors(false)
+            .thread_name("builder_test_receiver")
+            .build();
+
+        log_line(&logger, Level::Trace, "This message is filtered.")?;
+        log_line(&logger, Level::Debug, "This message is recorded.")?;
+        logger.shutdown()?;
+
+        // Verify the logfile was named from the template and uses the custom timestamp format
+        let logfile_contents = fs::read_to_string(log_dir.join("TEST_BUILDER.log"))?;
+        let header_regex =
+            Regex::new(r"^\d{2}:\d{2}:\d{2}: \[  DEBUG  \] mt_logger::tests::log_line\(\)")?;
+        assert!(header_regex.is_match(&logfile_contents));
+        assert!(logfile_contents.contains("This message is recorded."));
+        assert!(!logfile_contents.contains("This message is filtered."));
+
+        Ok(())
+    }
+
+    #[test]
+    fn logfile_naming_test() -> TestResult {
+        let (_mutex, base_dir) = lock_log_dir("logfile_naming_test")?;
+
+        // Start from a missing, nested log directory
+        let log_dir = base_dir.join("nested").join("logs");
+
+        let logger = MtLoggerBuilder::new("TEST_NAMING")
+            .output_stream(OutputStream::File)
+            .log_dir(&log_dir)
+            .run_dir_template("{date}")
+            .file_name_template("{prefix}_{pid}_{hostname}.log")
+            .build();
+        log_line(&logger, Level::Info, "This message is recorded.")?;
+        logger.shutdown()?;
+
+        // Verify the run directory was named after the current date
+        let run_dirs = list_files(&log_dir)?;
+        assert_eq!(
+            vec![chrono::Local::now().format("%Y-%m-%d").to_string()],
+            run_dirs
+        );
+
+        // Verify the logfile was named after the prefix, process ID, and a non-empty host name
+        let logfiles = list_files(&log_dir.join(&run_dirs[0]))?;
+        assert_eq!(1, logfiles.len());
+        let name_prefix = format!("TEST_NAMING_{}_", std::process::id());
+        assert!(logfiles[0].starts_with(&name_prefix));
+        assert!(logfiles[0].len() > name_prefix.len() + ".log".len());
+        assert!(logfiles[0].ends_with(".log"));
+
+        let logfile_contents = fs::read_to_string(log_dir.join(&run_dirs[0]).join(&logfiles[0]))?;
+        assert!(logfile_contents.contains("This message is recorded."));
+
+        Ok(())
+    }
+
+    #[test]
+    fn rotation_test() -> TestResult {
+        let (_mutex, log_dir) = lock_log_dir("rotation_test")?;
+
+        // Log the same messages with each rotation style, limiting logfiles to roughly two messages
+        for (logfile_prefix, rotation_style) in &[
+            ("TEST_ROTATION_INDEXED", RotationStyle::Indexed),
+            ("TEST_ROTATION_TIMESTAMPED", RotationStyle::Timestamped),
+        ] {
+            let logger = file_logger_builder(logfile_prefix, &log_dir)
+                .file_name_template("{prefix}_{timestamp}.log")
+                .timestamp_format("%H:%M:%S")
+                .rotation_size(200)
+                .rotation_style(*rotation_style)
+                .build();
+            for i in 0..5 {
+                log_line(&logger, Level::Info, format!("Rotated message #{}", i))?;
+            }
+            logger.shutdown()?;
+        }
+
+        // Collect the messages recorded in each logfile, along with its name and size
+        let mut logfiles = Vec::new();
+        for file_name in list_files(&log_dir)? {
+            let contents = fs::read_to_string(log_dir.join(&file_name))?;
+            let messages = recorded_msgs(&contents, &["Rotated message"]);
+            logfiles.push((file_name, contents.len(), messages));
+        }
+
+        for logfile_prefix in &["TEST_ROTATION_INDEXED", "TEST_ROTATION_TIMESTAMPED"] {
+            let prefix_logfiles = logfiles
+                .iter()
+                .filter(|(file_name, _, _)| file_name.starts_with(logfile_prefix))
+                .collect::<Vec<_>>();
+
+            // Every message must be recorded exactly once, without exceeding the size limit
+            assert_eq!(3, prefix_logfiles.len());
+            let mut all_messages = Vec::new();
+            for (_, size, messages) in &prefix_logfiles {
+                assert!(*size <= 200);
+                assert!(!messages.is_empty());
+                all_messages.extend(messages.iter().cloned());
+            }
+            all_messages.sort();
+            let expected_messages = (0..5)
+                .map(|i| format!("Rotated message #{}", i))
+                .collect::<Vec<_>>();
+            assert_eq!(expected_messages, all_messages);
+        }
+
+        // Indexed logfiles keep their name, with older logfiles having higher indices
+        let indexed_logfiles = logfiles
+            .iter()
+            .filter(|(file_name, _, _)| file_name.starts_with("TEST_ROTATION_INDEXED"))
+            .collect::<Vec<_>>();
+        assert!(indexed_logfiles[0].0.ends_with(".log"));
+        assert_eq!(
+            format!("{}.1", indexed_logfiles[0].0),
+            indexed_logfiles[1].0
+        );
+        assert_eq!(
+            format!("{}.2", indexed_logfiles[0].0),
+            indexed_logfiles[2].0
+        );
+        assert_eq!(vec!["Rotated message #4"], indexed_logfiles[0].2);
+        assert_eq!(
+            vec!["Rotated message #0", "Rotated message #1"],
+            indexed_logfiles[2].2
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn time_rotation_test() -> TestResult {
+        let (_mutex, log_dir) = lock_log_dir("time_rotation_test")?;
+
+        // Rotate daily on UTC boundaries, so that the result does not depend on the local timezone
+        let logger = file_logger_builder("TEST_TIME_ROTATION", &log_dir)
+            .rotation_interval(RotationInterval::Daily)
+            .rotation_utc(true)
+            .build();
+
+        // Log messages spanning three days after the logger started, including one from the
+        // previous day logged late
+        let year = Utc::now().year() + 1;
+        let timestamps = [
+            (27, 23, 59, 0),
+            (28, 0, 1, 0),
+            (28, 12, 0, 0),
+            (27, 23, 59, 59),
+            (29, 0, 0, 0),
+        ];
+        for (i, (day, hour, min, sec)) in timestamps.iter().enumerate() {
+            let timestamp = Utc
+                .with_ymd_and_hms(year, 6, *day, *hour, *min, *sec)
+                .unwrap()
+                .with_timezone(&Local);
+            logger.log_tuple(MsgTuple {
+                timestamp,
+                ..msg_tuple(Level::Info, format!("Timed message #{}", i))
+            })?;
+        }
+        logger.shutdown()?;
+
+        // The first logfile holds the first day, and the late message stays with the second day
+        let recorded = |file_name: &str| -> Result<Vec<String>, Box<dyn Error>> {
+            let contents = fs::read_to_string(log_dir.join(file_name))?;
+            Ok(recorded_msgs(&contents, &["Timed message"]))
+        };
+        assert_eq!(
+            vec!["Timed message #0"],
+            recorded("TEST_TIME_ROTATION.log.2")?
+        );
+        assert_eq!(
+            vec!["Timed message #1", "Timed message #2", "Timed message #3"],
+            recorded("TEST_TIME_ROTATION.log.1")?
+        );
+        assert_eq!(
+            vec!["Timed message #4"],
+            recorded("TEST_TIME_ROTATION.log")?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn retention_test() -> TestResult {
+        let (_mutex, log_dir) = lock_log_dir("retention_test")?;
+
+        // Logfiles from previous runs, named after process IDs above any real PID limit, along with
+        // files that must never be touched
+        create_aged_file(&log_dir, "TEST_RETENTION_9999991.log", 4)?;
+        create_aged_file(&log_dir, "TEST_RETENTION_9999992.log.1", 3)?;
+        create_aged_file(&log_dir, "TEST_RETENTION_9999993.log", 2)?;
+        create_aged_file(&log_dir, "TEST_RETENTION_9999994.log", 1)?;
+        create_aged_file(&log_dir, "TEST_RETENTION_notes.txt", 5)?;
+        create_aged_file(&log_dir, "OTHER_PREFIX_run1.log", 5)?;
+
+        // Keep the active logfile and the two newest logfiles
+        file_logger_builder("TEST_RETENTION", &log_dir)
+            .file_name_template("{prefix}_{pid}.log")
+            .max_logfiles(3)
+            .build()
+            .shutdown()?;
+        let active_file_name = format!("TEST_RETENTION_{}.log", std::process::id());
+        let mut expected_files = vec![
+            "OTHER_PREFIX_run1.log".to_string(),
+            "TEST_RETENTION_notes.txt".to_string(),
+            "TEST_RETENTION_9999993.log".to_string(),
+            "TEST_RETENTION_9999994.log".to_string(),
+            active_file_name.clone(),
+        ];
+        expected_files.sort();
+        assert_eq!(expected_files, list_files(&log_dir)?);
+
+        // Delete logfiles older than 90 minutes
+        file_logger_builder("TEST_RETENTION", &log_dir)
+            .file_name_template("{prefix}_{pid}.log")
+            .max_logfile_age(time::Duration::from_secs(90 * 60))
+            .build()
+            .shutdown()?;
+        expected_files.retain(|file_name| file_name != "TEST_RETENTION_9999993.log");
+        assert_eq!(expected_files, list_files(&log_dir)?);
+
+        // Cap the total size of logfiles while rotating
+        let logger = file_logger_builder("TEST_RETENTION", &log_dir)
+            .file_name_template("{prefix}_{pid}.log")
+            .timestamp_format("%H:%M:%S")
+            .rotation_size(200)
+            .max_total_logfile_size(400)
+            .build();
+        for i in 0..10 {
+            log_line(&logger, Level::Info, format!("Retained message #{}", i))?;
+        }
+        logger.shutdown()?;
+
+        // Only the newest messages remain, within the cap plus the active logfile's own limit
+        let mut total_size = 0;
+        let mut all_contents = String::new();
+        for file_name in list_files(&log_dir)? {
+            if file_name.starts_with("TEST_RETENTION_") && file_name != "TEST_RETENTION_notes.txt" {
+                let contents = fs::read_to_string(log_dir.join(&file_name))?;
+                total_size += contents.len();
+                all_contents.push_str(&contents);
+            }
+        }
+        assert!(total_size <= 400 + 200);
+        assert!(all_contents.contains("Retained message #9"));
+        assert!(!all_contents.contains("Retained message #0"));
+        assert!(log_dir.join("TEST_RETENTION_notes.txt").exists());
+        assert!(log_dir.join("OTHER_PREFIX_run1.log").exists());
+        assert!(!log_dir.join("TEST_RETENTION_9999994.log").exists());
+
+        Ok(())
+    }
 
+    #[test]
+    fn retention_prefix_test() -> TestResult {
+        let (_mutex, log_dir) = lock_log_dir("retention_prefix_test")?;
+
+        let count_files = |prefix: &str| -> Result<usize, Box<dyn Error>> {
+            let file_names = list_files(&log_dir)?;
+            Ok(file_names
+                .iter()
+                .filter(|file_name| file_name.starts_with(prefix))
+                .count())
+        };
 
-    // Reset verification files, if they exist
-    fn reset_verf_files() -> TestResult {
-        let path_buf = PathBuf::from(STDOUT_FILENAME);
-        if path_buf.as_path().exists() {
-            fs::write(STDOUT_FILENAME, "")?;
+        // One logger's prefix starts with the other's, followed by the separator in the template
+        create_aged_file(&log_dir, "TEST_PREFIX_2020-01-01T00_00_00.000+0000.log", 3)?;
+        create_aged_file(
+            &log_dir,
+            "TEST_PREFIX_2020-01-01T01_00_00.000-0500.log.1",
+            4,
+        )?;
+        create_aged_file(
+            &log_dir,
+            "TEST_PREFIX_worker_2020-01-01T00_00_00.000+0000.log",
+            5,
+        )?;
+        create_aged_file(
+            &log_dir,
+            "TEST_PREFIX_worker_2020-01-01T01_00_00.000-0500.log.1",
+            6,
+        )?;
+        create_aged_file(&log_dir, "TEST_PREFIX_2020-01-01.log", 7)?;
+
+        // Only the active logfile of the shorter prefix remains, along with files of another shape
+        MtLoggerBuilder::new("TEST_PREFIX")
+            .output_stream(OutputStream::File)
+            .log_dir(&log_dir)
+            .max_logfiles(1)
+            .build()
+            .shutdown()?;
+        assert!(!log_dir
+            .join("TEST_PREFIX_2020-01-01T00_00_00.000+0000.log")
+            .exists());
+        assert!(!log_dir
+            .join("TEST_PREFIX_2020-01-01T01_00_00.000-0500.log.1")
+            .exists());
+        assert!(log_dir.join("TEST_PREFIX_2020-01-01.log").exists());
+        assert_eq!(2, count_files("TEST_PREFIX_worker_")?);
+
+        // The same holds the other way around
+        MtLoggerBuilder::new("TEST_PREFIX_worker")
+            .output_stream(OutputStream::File)
+            .log_dir(&log_dir)
+            .max_logfiles(1)
+            .build()
+            .shutdown()?;
+        assert_eq!(1, count_files("TEST_PREFIX_worker_")?);
+        assert!(!log_dir
+            .join("TEST_PREFIX_worker_2020-01-01T00_00_00.000+0000.log")
+            .exists());
+        assert_eq!(3, count_files("TEST_PREFIX_")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn append_test() -> TestResult {
+        let (_mutex, log_dir) = lock_log_dir("append_test")?;
+
+        // Two runs writing to the same logfile, each followed by a run rotating to a new file
+        for run in 0..2 {
+            let logger = file_logger_builder("TEST_APPEND", &log_dir)
+                .append(true)
+                .latest_link("latest.log")
+                .build();
+            log_line(
+                &logger,
+                Level::Info,
+                format!("Appended message from run #{}", run),
+            )?;
+            logger.shutdown()?;
         }
 
-        let path_buf = PathBuf::from(FILE_OUT_FILENAME);
-        if path_buf.as_path().exists() {
-            fs::write(FILE_OUT_FILENAME, "")?;
+        let contents = fs::read_to_string(log_dir.join("TEST_APPEND.log"))?;
+        assert_eq!(
+            2,
+            contents.matches("Started new run of 'TEST_APPEND'").count()
+        );
+        let first_run_pos = contents.find("Appended message from run #0").unwrap();
+        let second_run_pos = contents.find("Appended message from run #1").unwrap();
+        assert!(first_run_pos < second_run_pos);
+        assert_eq!(
+            fs::read_link(log_dir.join("latest.log"))?,
+            PathBuf::from("TEST_APPEND.log")
+        );
+
+        // The link keeps pointing at the active logfile across rotations
+        let logger = file_logger_builder("TEST_APPEND", &log_dir)
+            .file_name_template("{prefix}_rotated.log")
+            .timestamp_format("%H:%M:%S")
+            .rotation_size(200)
+            .latest_link("latest.log")
+            .build();
+        for i in 0..5 {
+            log_line(&logger, Level::Info, format!("Rotated message #{}", i))?;
         }
+        logger.shutdown()?;
+
+        assert_eq!(
+            fs::read_link(log_dir.join("latest.log"))?,
+            PathBuf::from("TEST_APPEND_rotated.log")
+        );
+        assert!(log_dir.join("TEST_APPEND_rotated.log.1").exists());
+        let latest_contents = fs::read_to_string(log_dir.join("latest.log"))?;
+        assert!(latest_contents.contains("Rotated message #4"));
+        assert!(!latest_contents.contains("Rotated message #0"));
+        assert!(!log_dir.join("latest.log.tmp").exists());
 
         Ok(())
     }
 
-    fn format_verf_helper(
-        verf_type: VerfFile,
-        verf_string: String,
-        first_line_num: u32,
-    ) -> TestResult {
-        // Set up the verification items
-        const FN_NAME: &str = "mt_logger::tests::format_verification";
-        const VERF_MATRIX: [[&str; 3]; 6] = [
-            ["TRACE", "030;105m", "  TRACE  "],
-            ["DEBUG", "030;106m", "  DEBUG  "],
-            ["INFO", "030;107m", "  INFO   "],
-            ["WARNING", "030;103m", " WARNING "],
-            ["ERROR", "030;101m", "  ERROR  "],
-            ["FATAL", "031;040m", "  FATAL  "],
-        ];
-        const LEVEL_VERF_IDX: usize = 0;
-        const COLOR_VERF_IDX: usize = 1;
-        const PADDED_LEVEL_VERF_IDX: usize = 2;
-
-        // Set up output-specific parameters
-        let padded_level_hdr_capture_idx;
-        let fn_name_hdr_capture_idx;
-        let line_num_hdr_capture_idx;
-        let header_regex;
-        match verf_type {
-            VerfFile::StdOut => {
-                padded_level_hdr_capture_idx = STDOUT_PADDED_LEVEL_IDX;
-                fn_name_hdr_capture_idx = STDOUT_FN_NAME_IDX;
-                line_num_hdr_capture_idx = STDOUT_LINE_NUM_IDX;
-                header_regex = Regex::new(STDOUT_HDR_REGEX_STR)?;
-            }
-            VerfFile::FileOut => {
-                padded_level_hdr_capture_idx = FILE_OUT_PADDED_LEVEL_IDX;
-                fn_name_hdr_capture_idx = FILE_OUT_FN_NAME_IDX;
-                line_num_hdr_capture_idx = FILE_OUT_LINE_NUM_IDX;
-                header_regex = Regex::new(FILE_OUT_HDR_REGEX_STR)?;
+    #[test]
+    fn reopen_test() -> TestResult {
+        let (_mutex, log_dir) = lock_log_dir("reopen_test")?;
+        let logfile_path = log_dir.join("TEST_REOPEN.log");
+
+        let builder = file_logger_builder("TEST_REOPEN", &log_dir);
+        #[cfg(target_os = "linux")]
+        let builder = builder.reopen_on_signal(ReopenSignal::User1);
+        let logger = builder.build();
+        let log_numbered_msg =
+            |i: u32| log_line(&logger, Level::Info, format!("Reopened message #{}", i));
+
+        // Messages keep going to the renamed logfile until it is reopened
+        log_numbered_msg(0)?;
+        logger.flush()?;
+        fs::rename(&logfile_path, log_dir.join("TEST_REOPEN.log.1"))?;
+        log_numbered_msg(1)?;
+        logger.reopen_files()?;
+        log_numbered_msg(2)?;
+        logger.flush()?;
+
+        let rotated_contents = fs::read_to_string(log_dir.join("TEST_REOPEN.log.1"))?;
+        assert!(rotated_contents.contains("Reopened message #0"));
+        assert!(rotated_contents.contains("Reopened message #1"));
+        assert!(!rotated_contents.contains("Reopened message #2"));
+        let reopened_contents = fs::read_to_string(&logfile_path)?;
+        assert!(reopened_contents.contains("Reopened message #2"));
+        assert!(!reopened_contents.contains("Reopened message #1"));
+
+        // The signal handler reopens the logfile the next time the logging thread wakes
+        #[cfg(target_os = "linux")]
+        {
+            fs::rename(&logfile_path, log_dir.join("TEST_REOPEN.log.2"))?;
+            // SAFETY: The logger installed a handler for SIGUSR1 when it was built
+            assert_eq!(0, unsafe { libc::raise(libc::SIGUSR1) });
+            let deadline = time::Instant::now() + time::Duration::from_secs(5);
+            while !logfile_path.exists() && time::Instant::now() < deadline {
+                thread::sleep(time::Duration::from_millis(10));
             }
+            log_numbered_msg(3)?;
+            logger.flush()?;
+
+            let signaled_contents = fs::read_to_string(&logfile_path)?;
+            assert!(signaled_contents.contains("Reopened message #3"));
+            assert!(!signaled_contents.contains("Reopened message #2"));
         }
-        let level_content_capture_idx = 1;
 
-        // Create regex for message content
-        let content_regex = Regex::new(r"^   This is an? (\w*) message.")?;
+        logger.shutdown()?;
 
-        // Read verf string into iterator
-        let mut verf_lines: Vec<&str> = verf_string.split('\n').collect();
-        let mut verf_line_iter = verf_lines.iter_mut();
+        Ok(())
+    }
 
-        // Iterate over lines, verifying along the way
-        let mut i = 0;
-        while let Some(header_line) = verf_line_iter.next().filter(|v| !v.is_empty()) {
-            // Match regex against header line, and capture groups
-            let header_captures = header_regex.captures(header_line).unwrap_or_else(|| {
-                panic!(
-                    "{:?}: Header line {} '{}' did not match Regex:\n   {}",
-                    verf_type,
-                    i,
-                    header_line,
-                    header_regex.as_str()
-                )
-            });
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn reopen_signal_restore_test() -> TestResult {
+        // Lock logger mutex, as the new receivers will reset the verification files
+        let _mutex = LOGGER_MUTEX.lock()?;
 
-            // Verify capture groups
-            if verf_type == VerfFile::StdOut
-                && &header_captures[STDOUT_COLOR_IDX] != VERF_MATRIX[i][COLOR_VERF_IDX]
-            {
-                panic!(
-                    "{:?}: Wrong color '{}' on line '{}', should be '{}'",
-                    verf_type,
-                    &header_captures[STDOUT_COLOR_IDX],
-                    header_line,
-                    VERF_MATRIX[i][COLOR_VERF_IDX]
+        let current_handler = || {
+            // SAFETY: Only queries the disposition, into a zeroed plain-data struct
+            unsafe {
+                let mut action: libc::sigaction = std::mem::zeroed();
+                assert_eq!(
+                    0,
+                    libc::sigaction(libc::SIGUSR1, std::ptr::null(), &mut action)
                 );
+                action.sa_sigaction
             }
-            if &header_captures[padded_level_hdr_capture_idx]
-                != VERF_MATRIX[i][PADDED_LEVEL_VERF_IDX]
-            {
-                panic!(
-                    "{:?}: Wrong padded level '{}' on line '{}', should be '{}'",
-                    verf_type,
-                    &header_captures[padded_level_hdr_capture_idx],
-                    header_line,
-                    VERF_MATRIX[i][PADDED_LEVEL_VERF_IDX]
-                );
+        };
+        let original_handler = current_handler();
+        let reopen_builder = || {
+            MtLoggerBuilder::new("TEST")
+                .output_stream(OutputStream::StdOut)
+                .reopen_on_signal(ReopenSignal::User1)
+        };
+
+        // Make sure there is an instance to replace
+        if INSTANCE.get().is_none() {
+            mt_new!(LOGFILE_PREFIX, Level::Info, OutputStream::StdOut);
+        }
+
+        // The handler stays installed while any logger relies on it, even across a replacement
+        reopen_builder().try_replace()?;
+        let reopen_handler = current_handler();
+        assert_ne!(original_handler, reopen_handler);
+        reopen_builder().try_replace()?;
+        assert_eq!(reopen_handler, current_handler());
+
+        // Replacing the last such logger restores the previous disposition
+        mt_replace!(LOGFILE_PREFIX, Level::Info, OutputStream::StdOut)?;
+        assert_eq!(original_handler, current_handler());
+
+        // As does shutting it down
+        let logger = reopen_builder().build();
+        assert_eq!(reopen_handler, current_handler());
+        logger.shutdown()?;
+        assert_eq!(original_handler, current_handler());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn compression_test() -> TestResult {
+        use flate2::read::GzDecoder;
+
+        let (_mutex, log_dir) = lock_log_dir("compression_test")?;
+
+        // Compress logfiles holding roughly two messages each as they are rotated
+        let logger = file_logger_builder("TEST_COMPRESSION", &log_dir)
+            .timestamp_format("%H:%M:%S")
+            .rotation_size(200)
+            .compress_rotated(true)
+            .build();
+        for i in 0..5 {
+            log_line(&logger, Level::Info, format!("Compressed message #{}", i))?;
+        }
+        logger.shutdown()?;
+
+        let recorded = |file_name: &str| -> Result<Vec<String>, Box<dyn Error>> {
+            let mut contents = String::new();
+            let file = fs::File::open(log_dir.join(file_name))?;
+            if file_name.ends_with(".gz") {
+                GzDecoder::new(file).read_to_string(&mut contents)?;
+            } else {
+                std::io::BufReader::new(file).read_to_string(&mut contents)?;
             }
-            if &header_captures[fn_name_hdr_capture_idx] != FN_NAME {
-                panic!(
-                    "{:?}: Wrong function name '{}' on line '{}', should be '{}'",
-                    verf_type, &header_captures[fn_name_hdr_capture_idx], header_line, FN_NAME
-                );
+            Ok(recorded_msgs(&contents, &["Compressed message"]))
+        };
+
+        // Rotated logfiles are compressed once the logger shuts down, keeping their indices
+        assert_eq!(
+            vec![
+                "TEST_COMPRESSION.log",
+                "TEST_COMPRESSION.log.1.gz",
+                "TEST_COMPRESSION.log.2.gz"
+            ],
+            list_files(&log_dir)?
+        );
+        assert_eq!(
+            vec!["Compressed message #0", "Compressed message #1"],
+            recorded("TEST_COMPRESSION.log.2.gz")?
+        );
+        assert_eq!(
+            vec!["Compressed message #2", "Compressed message #3"],
+            recorded("TEST_COMPRESSION.log.1.gz")?
+        );
+        assert_eq!(
+            vec!["Compressed message #4"],
+            recorded("TEST_COMPRESSION.log")?
+        );
+
+        // Retention applies to compressed logfiles as well
+        file_logger_builder("TEST_COMPRESSION", &log_dir)
+            .max_logfiles(2)
+            .build()
+            .shutdown()?;
+        assert_eq!(
+            vec!["TEST_COMPRESSION.log", "TEST_COMPRESSION.log.1.gz"],
+            list_files(&log_dir)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn durability_test() -> TestResult {
+        let (_mutex, log_dir) = lock_log_dir("durability_test")?;
+
+        // Every buffered message must be in the logfile once a flush completes, for every policy
+        for (idx, durability_policy) in [
+            DurabilityPolicy::Never,
+            DurabilityPolicy::OnError,
+            DurabilityPolicy::Interval(time::Duration::from_millis(10)),
+            DurabilityPolicy::EveryMessage,
+        ]
+        .iter()
+        .enumerate()
+        {
+            let logfile_prefix = format!("TEST_DURABILITY_{}", idx);
+            let logger = file_logger_builder(&logfile_prefix, &log_dir)
+                .durability_policy(*durability_policy)
+                .build();
+
+            for msg_idx in 0..100 {
+                log_line(
+                    &logger,
+                    if msg_idx % 10 == 0 {
+                        Level::Error
+                    } else {
+                        Level::Info
+                    },
+                    format!("Durable message {:03}", msg_idx),
+                )?;
             }
-            if header_captures[line_num_hdr_capture_idx].parse::<u32>()?
-                != first_line_num + i as u32
-            {
-                panic!(
-                    "{:?}: Wrong line number '{}' on line '{}', should be '{}'",
-                    verf_type,
-                    &header_captures[line_num_hdr_capture_idx],
-                    header_line,
-                    first_line_num + i as u32
+            logger.flush()?;
+
+            let logfile_contents =
+                fs::read_to_string(log_dir.join(format!("{}.log", logfile_prefix)))?;
+            for msg_idx in 0..100 {
+                assert!(
+                    logfile_contents.contains(&format!("Durable message {:03}", msg_idx)),
+                    "Message {} missing from logfile with {:?}",
+                    msg_idx,
+                    durability_policy
                 );
             }
 
-            // Verify content line
-            let content_line = verf_line_iter
-                .next()
-                .unwrap_or_else(|| panic!("Missing content line after header '{}'", header_line));
-            let content_captures = content_regex.captures(content_line).unwrap_or_else(|| {
-                panic!(
-                    "{:?}: Content line {} '{}' did not match content Regex:\n   {}",
-                    verf_type,
-                    i,
-                    content_line,
-                    content_regex.as_str()
-                )
-            });
+            logger.shutdown()?;
+        }
 
-            if &content_captures[level_content_capture_idx] != VERF_MATRIX[i][LEVEL_VERF_IDX] {
-                panic!(
-                    "{:?}: Wrong level '{}' in content line '{}', should be '{}'",
-                    verf_type,
-                    &content_captures[level_content_capture_idx],
-                    content_line,
-                    VERF_MATRIX[i][LEVEL_VERF_IDX]
-                )
+        Ok(())
+    }
+
+    #[test]
+    fn record_details_test() -> TestResult {
+        let (_mutex, log_dir) = lock_log_dir("record_details_test")?;
+
+        // Build a standalone logger that renders every optional detail to a fixed file
+        let logger = Arc::new(
+            file_logger_builder("TEST_RECORD_DETAILS", &log_dir)
+                .thread_info(true)
+                .source_location(true)
+                .module_path(true)
+                .build(),
+        );
+
+        // Log from a named thread, without the macro's module path
+        let thread_logger = Arc::clone(&logger);
+        thread::Builder::new()
+            .name("details_thread".to_string())
+            .spawn(move || log_line(&thread_logger, Level::Info, "Logged from a named thread."))?
+            .join()
+            .map_err(|_| "Logging thread panicked")??;
+
+        // Log a fully-populated tuple from an unnamed thread
+        thread::spawn({
+            let thread_logger = Arc::clone(&logger);
+            move || {
+                thread_logger.log_tuple(MsgTuple {
+                    target: "mt_logger::details".to_string(),
+                    line: 7,
+                    column: 9,
+                    file: "src/details.rs",
+                    module_path: "mt_logger::details",
+                    ..msg_tuple(Level::Info, "Logged from an unnamed thread.")
+                })
             }
+        })
+        .join()
+        .map_err(|_| "Logging thread panicked")??;
+        logger.shutdown()?;
+
+        // Verify that the details are rendered in the header
+        let logfile_contents = fs::read_to_string(log_dir.join("TEST_RECORD_DETAILS.log"))?;
+        let named_regex = Regex::new(
+            r"log_line\(\) line \d+ on thread 'details_thread' \(ThreadId\(\d+\)\) at src[/\\]lib\.rs:\d+:\d+:\n +Logged from a named thread\.",
+        )?;
+        assert!(named_regex.is_match(&logfile_contents));
+        let unnamed_regex = Regex::new(
+            r"msg_tuple\(\) line 7 on unnamed thread \(ThreadId\(\d+\)\) at src/details\.rs:7:9 in mt_logger::details:\n +Logged from an unnamed thread\.",
+        )?;
+        assert!(unnamed_regex.is_match(&logfile_contents));
 
-            i += 1;
+        Ok(())
+    }
+
+    #[test]
+    fn target_filter_test() -> TestResult {
+        let (_mutex, log_dir) = lock_log_dir("target_filter_test")?;
+
+        // Build a standalone logger that traces the network layer, but only warns for its TCP layer
+        let logger = file_logger_builder("TEST_TARGET_FILTER", &log_dir)
+            .output_level(Level::Info)
+            .target_level("app::net", Level::Trace)
+            .target_level("app::net::tcp", Level::Warning)
+            .build();
+
+        // The sending side must let through anything that any target could record
+        assert!(logger.is_enabled(Level::Trace));
+
+        let targeted_msg = |target: &str, level: Level, msg: &str| MsgTuple {
+            target: target.to_string(),
+            ..msg_tuple(level, msg)
+        };
+        logger.log_tuple(targeted_msg("app::net", Level::Trace, "net trace recorded"))?;
+        logger.log_tuple(targeted_msg(
+            "app::net::udp",
+            Level::Debug,
+            "udp debug recorded",
+        ))?;
+        logger.log_tuple(targeted_msg(
+            "app::net::tcp",
+            Level::Info,
+            "tcp info filtered",
+        ))?;
+        logger.log_tuple(targeted_msg(
+            "app::net::tcp::conn",
+            Level::Error,
+            "tcp error recorded",
+        ))?;
+        logger.log_tuple(targeted_msg(
+            "app::network",
+            Level::Debug,
+            "network debug filtered",
+        ))?;
+        logger.log_tuple(targeted_msg("app::db", Level::Info, "db info recorded"))?;
+
+        // Update the database layer at runtime, which must apply to subsequent messages only
+        logger.log_cmd(Command::SetTargetLevel(
+            "app::db".to_string(),
+            Level::Warning,
+        ))?;
+        logger.log_tuple(targeted_msg("app::db", Level::Info, "db info filtered"))?;
+        logger.log_tuple(targeted_msg(
+            "app::db",
+            Level::Warning,
+            "db warning recorded",
+        ))?;
+
+        // Quieting every target must also quiet the sending side
+        logger.log_cmd(Command::SetTargetLevel("app::net".to_string(), Level::Info))?;
+        assert!(!logger.is_enabled(Level::Debug));
+        logger.shutdown()?;
+
+        let logfile_contents = fs::read_to_string(log_dir.join("TEST_TARGET_FILTER.log"))?;
+        for recorded in [
+            "net trace recorded",
+            "udp debug recorded",
+            "tcp error recorded",
+            "db info recorded",
+            "db warning recorded",
+        ]
+        .iter()
+        {
+            assert!(
+                logfile_contents.contains(recorded),
+                "Missing '{}'",
+                recorded
+            );
+        }
+        assert!(!logfile_contents.contains("filtered"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn level_mask_test() -> TestResult {
+        let (_mutex, log_dir) = lock_log_dir("level_mask_test")?;
+
+        // Thresholds and combinations produce the expected sets
+        assert_eq!(LevelMask::all(), LevelMask::at_or_above(Level::Trace));
+        assert_eq!(
+            LevelMask::from(Level::Fatal),
+            LevelMask::at_or_above(Level::Fatal)
+        );
+        assert_eq!(
+            Level::Warning | Level::Error | Level::Fatal,
+            LevelMask::at_or_above(Level::Warning)
+        );
+        let mut mask = LevelMask::empty();
+        assert!(mask.is_empty());
+        mask |= Level::Debug;
+        assert_eq!(Level::Debug | Level::Info, mask.with(Level::Info));
+        assert_eq!(LevelMask::empty(), mask.without(Level::Debug));
+
+        // Build a standalone logger that records only Trace and Error messages
+        let logger = file_logger_builder("TEST_LEVEL_MASK", &log_dir)
+            .output_level_mask(Level::Trace | Level::Error)
+            .build();
+        assert!(logger.is_enabled(Level::Trace));
+        assert!(!logger.is_enabled(Level::Debug));
+        assert!(!logger.is_enabled(Level::Fatal));
+
+        const LEVELS: [Level; 6] = [
+            Level::Trace,
+            Level::Debug,
+            Level::Info,
+            Level::Warning,
+            Level::Error,
+            Level::Fatal,
+        ];
+        let log_every_level = |phase: &str| -> TestResult {
+            for level in LEVELS.iter() {
+                log_line(&logger, *level, format!("{} {}", phase, level))?;
+            }
+            Ok(())
+        };
+        log_every_level("masked")?;
+
+        // Exclude Info while keeping the threshold behavior otherwise
+        logger.log_cmd(Command::SetLevelMask(
+            LevelMask::at_or_above(Level::Debug).without(Level::Info),
+        ))?;
+        log_every_level("excluded")?;
+        logger.shutdown()?;
+
+        let logfile_contents = fs::read_to_string(log_dir.join("TEST_LEVEL_MASK.log"))?;
+        let recorded = recorded_msgs(&logfile_contents, &["masked", "excluded"]);
+        assert_eq!(
+            vec![
+                "masked TRACE",
+                "masked ERROR",
+                "excluded DEBUG",
+                "excluded WARNING",
+                "excluded ERROR",
+                "excluded FATAL",
+            ],
+            recorded
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn static_max_level_test() -> TestResult {
+        // Lock logger mutex, as the replacement receiver will reset the verification files
+        let _mutex = LOGGER_MUTEX.lock()?;
+
+        // The compiled maximum matches the most restrictive enabled feature, with the release
+        // features only applying to release builds
+        let release = !cfg!(debug_assertions);
+        let features = [
+            (
+                release && cfg!(feature = "release_max_level_off"),
+                crate::LEVEL_OFF,
+            ),
+            (
+                release && cfg!(feature = "release_max_level_fatal"),
+                Level::Fatal as u8,
+            ),
+            (
+                release && cfg!(feature = "release_max_level_error"),
+                Level::Error as u8,
+            ),
+            (
+                release && cfg!(feature = "release_max_level_warning"),
+                Level::Warning as u8,
+            ),
+            (
+                release && cfg!(feature = "release_max_level_info"),
+                Level::Info as u8,
+            ),
+            (
+                release && cfg!(feature = "release_max_level_debug"),
+                Level::Debug as u8,
+            ),
+            (
+                release && cfg!(feature = "release_max_level_trace"),
+                Level::Trace as u8,
+            ),
+            (cfg!(feature = "max_level_off"), crate::LEVEL_OFF),
+            (cfg!(feature = "max_level_fatal"), Level::Fatal as u8),
+            (cfg!(feature = "max_level_error"), Level::Error as u8),
+            (cfg!(feature = "max_level_warning"), Level::Warning as u8),
+            (cfg!(feature = "max_level_info"), Level::Info as u8),
+            (cfg!(feature = "max_level_debug"), Level::Debug as u8),
+        ];
+        let expected_level = features
+            .iter()
+            .find(|(enabled, _)| *enabled)
+            .map_or(Level::Trace as u8, |(_, level)| *level);
+        assert_eq!(expected_level, crate::STATIC_MAX_LEVEL);
+
+        // Calls below the compiled maximum must not even evaluate their arguments, so only the
+        // compiled maximum applies while the instance records every level
+        mt_replace!(LOGFILE_PREFIX, Level::Trace, OutputStream::StdOut)?;
+        const LEVELS: [Level; 6] = [
+            Level::Trace,
+            Level::Debug,
+            Level::Info,
+            Level::Warning,
+            Level::Error,
+            Level::Fatal,
+        ];
+        let mut evaluated_levels = Vec::new();
+        for level in LEVELS.iter() {
+            mt_log!(*level, "Evaluated {}", {
+                evaluated_levels.push(*level);
+                level
+            });
+        }
+        let expected_levels: Vec<Level> = LEVELS
+            .iter()
+            .copied()
+            .filter(|level| *level as u8 >= crate::STATIC_MAX_LEVEL)
+            .collect();
+        assert_eq!(expected_levels, evaluated_levels);
+
+        Ok(())
+    }
+
+    #[test]
+    fn stream_level_test() -> TestResult {
+        // Lock logger mutex and hold it until we're done reading the verification files
+        let mutex = LOGGER_MUTEX.lock()?;
+
+        // Build a standalone logger that keeps everything in the file, but only warns on console
+        let logger = MtLoggerBuilder::new("TEST_STREAM_LEVEL")
+            .output_level(Level::Trace)
+            .output_stream(OutputStream::Both)
+            .stream_level(OutputStream::StdOut, Level::Warning)
+            .build();
+        reset_verf_files()?;
+
+        log_line(&logger, Level::Trace, "init trace")?;
+        log_line(&logger, Level::Warning, "init warning")?;
+
+        // Exclude Warning from the file and restore the console threshold at runtime
+        logger.log_cmd(Command::SetStreamLevelMask(
+            OutputStream::File,
+            LevelMask::all().without(Level::Warning),
+        ))?;
+        logger.log_cmd(Command::SetStreamLevel(OutputStream::StdOut, Level::Trace))?;
+        log_line(&logger, Level::Trace, "runtime trace")?;
+        log_line(&logger, Level::Warning, "runtime warning")?;
+        logger.shutdown()?;
+
+        let verf_string_stdout = fs::read_to_string(STDOUT_FILENAME)?;
+        let verf_string_file_out = fs::read_to_string(FILE_OUT_FILENAME)?;
+        std::mem::drop(mutex);
+
+        let recorded = |verf_string: &str| recorded_msgs(verf_string, &["init", "runtime"]);
+        assert_eq!(
+            vec!["init warning", "runtime trace", "runtime warning"],
+            recorded(&verf_string_stdout)
+        );
+        assert_eq!(
+            vec!["init trace", "init warning", "runtime trace"],
+            recorded(&verf_string_file_out)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn stderr_test() -> TestResult {
+        // Lock logger mutex and hold it until we're done reading the verification files
+        let mutex = LOGGER_MUTEX.lock()?;
+
+        // Build a standalone logger that splits messages between the console streams
+        let logger = MtLoggerBuilder::new("TEST_STDERR")
+            .output_level(Level::Trace)
+            .output_stream(OutputStream::Split)
+            .colors(false)
+            .build();
+        reset_verf_files()?;
+
+        log_line(&logger, Level::Info, "split info")?;
+        log_line(&logger, Level::Warning, "split warning")?;
+
+        // Send everything to StdErr, but only errors and higher
+        logger.log_cmd(Command::SetOutputStream(OutputStream::StdErrAndFile))?;
+        logger.log_cmd(Command::SetStreamLevel(OutputStream::StdErr, Level::Error))?;
+        log_line(&logger, Level::Warning, "stderr warning")?;
+        log_line(&logger, Level::Error, "stderr error")?;
+        logger.shutdown()?;
+
+        let verf_string_stdout = fs::read_to_string(STDOUT_FILENAME)?;
+        let verf_string_stderr = fs::read_to_string(STDERR_FILENAME)?;
+        let verf_string_file_out = fs::read_to_string(FILE_OUT_FILENAME)?;
+        std::mem::drop(mutex);
+
+        let recorded = |verf_string: &str| recorded_msgs(verf_string, &["split", "stderr"]);
+        assert_eq!(vec!["split info"], recorded(&verf_string_stdout));
+        assert_eq!(
+            vec!["split warning", "stderr error"],
+            recorded(&verf_string_stderr)
+        );
+        assert_eq!(
+            vec!["stderr warning", "stderr error"],
+            recorded(&verf_string_file_out)
+        );
+
+        // Warnings and higher are formatted the same as on StdOut
+        assert!(verf_string_stderr.contains("[ WARNING ] mt_logger::tests::log_line() line"));
+
+        Ok(())
+    }
+
+    // Sink recording the content of each message, and whether it has been closed
+    struct CollectorSink {
+        records: Arc<Mutex<Vec<String>>>,
+        closed: Arc<Mutex<bool>>,
+    }
+
+    impl Sink for CollectorSink {
+        fn record(&mut self, log_tuple: &MsgTuple) -> std::io::Result<()> {
+            self.records.lock().unwrap().push(log_tuple.msg.clone());
+            Ok(())
+        }
+
+        fn close(&mut self) -> std::io::Result<()> {
+            *self.closed.lock().unwrap() = true;
+            Ok(())
         }
+    }
+
+    #[test]
+    fn sink_test() -> TestResult {
+        // Lock logger mutex and hold it until we're done reading the verification files
+        let mutex = LOGGER_MUTEX.lock()?;
+
+        // Register one sink at build time that only records errors
+        let static_records = Arc::new(Mutex::new(Vec::new()));
+        let static_closed = Arc::new(Mutex::new(false));
+        let static_id = SinkId::unique();
+        let logger = MtLoggerBuilder::new("TEST_SINK")
+            .output_level(Level::Trace)
+            .output_stream(OutputStream::Both)
+            .sink(
+                static_id,
+                CollectorSink {
+                    records: Arc::clone(&static_records),
+                    closed: Arc::clone(&static_closed),
+                },
+            )
+            .sink_level_mask(static_id, LevelMask::at_or_above(Level::Error))
+            .build();
+        reset_verf_files()?;
+
+        log_line(&logger, Level::Info, "first info")?;
+        log_line(&logger, Level::Error, "first error")?;
+
+        // Add a second sink at runtime, then remove it again
+        let runtime_records = Arc::new(Mutex::new(Vec::new()));
+        let runtime_closed = Arc::new(Mutex::new(false));
+        let runtime_id = logger.add_sink(Box::new(CollectorSink {
+            records: Arc::clone(&runtime_records),
+            closed: Arc::clone(&runtime_closed),
+        }))?;
+        assert_ne!(static_id, runtime_id);
+        log_line(&logger, Level::Info, "second info")?;
+        logger.remove_sink(runtime_id)?;
+        log_line(&logger, Level::Error, "second error")?;
+
+        // Stop recording to the built-in console sink entirely
+        logger.log_cmd(Command::SetSinkLevelMask(
+            SinkId::CONSOLE,
+            LevelMask::empty(),
+        ))?;
+        log_line(&logger, Level::Error, "third error")?;
+        logger.flush()?;
+        assert!(*runtime_closed.lock().unwrap());
+        assert!(!*static_closed.lock().unwrap());
+        logger.shutdown()?;
+        assert!(*static_closed.lock().unwrap());
+
+        let verf_string_stdout = fs::read_to_string(STDOUT_FILENAME)?;
+        let verf_string_file_out = fs::read_to_string(FILE_OUT_FILENAME)?;
+        std::mem::drop(mutex);
+
+        let recorded =
+            |verf_string: &str| recorded_msgs(verf_string, &["first", "second", "third"]);
+        assert_eq!(
+            vec!["first error", "second error", "third error"],
+            *static_records.lock().unwrap()
+        );
+        assert_eq!(vec!["second info"], *runtime_records.lock().unwrap());
+        assert_eq!(
+            vec!["first info", "first error", "second info", "second error"],
+            recorded(&verf_string_stdout)
+        );
+        assert_eq!(
+            vec![
+                "first info",
+                "first error",
+                "second info",
+                "second error",
+                "third error"
+            ],
+            recorded(&verf_string_file_out)
+        );
 
         Ok(())
     }
 
     #[test]
-    fn format_verification() -> TestResult {
-        // Lock logger mutex and hold it until we're done processing messages
-        let mutex = LOGGER_MUTEX.lock()?;
+    fn directives_test() -> TestResult {
+        // Lock logger mutex, as the environment variable affects every initialization macro
+        let _mutex = LOGGER_MUTEX.lock()?;
 
-        // Clean verification files before test
-        reset_verf_files()?;
+        // Level names are case-insensitive
+        assert_eq!(Level::Warning, "WARN".parse::<Level>()?);
+        assert_eq!(Level::Fatal, "Fatal".parse::<Level>()?);
+        assert!("verbose".parse::<Level>().is_err());
+
+        // Valid directives are parsed in order, ignoring whitespace and empty directives
+        assert_eq!(
+            Directives {
+                default_level: Some(Level::Info),
+                target_levels: vec![
+                    ("my_app::net".to_string(), Level::Trace),
+                    ("my_app::db".to_string(), Level::Warning),
+                ],
+            },
+            filter::parse_directives(" my_app::net=trace, ,info,my_app::db = warning,")?
+        );
 
-        // Create or update logger instance such that all messages are logged to Both outputs
-        if INSTANCE.get().is_none() {
-            mt_new!(LOGFILE_PREFIX, Level::Trace, OutputStream::Both);
-        } else {
-            mt_level!(Level::Trace);
-            mt_stream!(OutputStream::Both);
+        // Invalid directives are reported with the offending directive
+        for (directives, invalid) in [
+            ("info,my_app::net=verbose", "my_app::net=verbose"),
+            ("inof", "inof"),
+            ("=debug", "=debug"),
+            ("my app=debug", "my app=debug"),
+            ("my_app=debug=trace", "my_app=debug=trace"),
+        ]
+        .iter()
+        {
+            match filter::parse_directives(directives) {
+                Err(MtLoggerError::InvalidDirective(directive)) => assert_eq!(invalid, &directive),
+                other => panic!(
+                    "Expected InvalidDirective for '{}', got {:?}",
+                    directives, other
+                ),
+            }
         }
 
-        let first_line_num = line!() + 1;
-        mt_log!(Level::Trace, "This is a TRACE message.");
-        mt_log!(Level::Debug, "This is a DEBUG message.");
-        mt_log!(Level::Info, "This is an INFO message.");
-        mt_log!(Level::Warning, "This is a WARNING message.");
-        mt_log!(Level::Error, "This is an ERROR message.");
-        mt_log!(Level::Fatal, "This is a FATAL message.");
+        // Directives from the environment override the configured levels, and are validated
+        std::env::set_var(crate::DIRECTIVES_ENV_VAR, "warning,my_app::net=debug");
+        let logger = MtLoggerBuilder::new("TEST_DIRECTIVES")
+            .output_level(Level::Trace)
+            .output_stream(OutputStream::Neither)
+            .env_directives(crate::DIRECTIVES_ENV_VAR)
+            .build();
+        std::env::set_var(crate::DIRECTIVES_ENV_VAR, "my_app::net=verbose");
+        let build_result = MtLoggerBuilder::new("TEST_DIRECTIVES")
+            .env_directives(crate::DIRECTIVES_ENV_VAR)
+            .try_build();
+        std::env::remove_var(crate::DIRECTIVES_ENV_VAR);
+        assert!(matches!(
+            build_result,
+            Err(MtLoggerError::InvalidDirective(_))
+        ));
+        assert!(logger.is_enabled(Level::Debug));
+        assert!(!logger.is_enabled(Level::Trace));
+
+        // Directives applied at runtime update the sending side, unless any is invalid
+        logger.apply_directives("error,my_app::net=warning")?;
+        assert!(!logger.is_enabled(Level::Info));
+        assert!(logger.is_enabled(Level::Warning));
+        assert!(logger.apply_directives("trace,my_app::db=bogus").is_err());
+        assert!(!logger.is_enabled(Level::Info));
+        logger.shutdown()?;
 
-        // Flush the messages to their output
-        println!("Flushing all messages to their output...");
-        let start_time = time::Instant::now();
-        mt_flush!()?;
-        println!("Done flushing after {}ms", start_time.elapsed().as_millis());
+        Ok(())
+    }
 
-        // Capture the files in memory before releasing the mutex
-        let mut verf_file_stdout = fs::OpenOptions::new().read(true).open(STDOUT_FILENAME)?;
-        let mut verf_string_stdout = String::new();
-        verf_file_stdout.read_to_string(&mut verf_string_stdout)?;
-        let mut verf_file_file_out = fs::OpenOptions::new().read(true).open(FILE_OUT_FILENAME)?;
-        let mut verf_string_file_out = String::new();
-        verf_file_file_out.read_to_string(&mut verf_string_file_out)?;
+    #[test]
+    fn init_error_test() -> TestResult {
+        // Lock logger mutex, as the global instance may be initialized by this test
+        let _mutex = LOGGER_MUTEX.lock()?;
 
-        // Unlock the mutex
-        std::mem::drop(mutex);
+        // A log directory that is actually a file must be reported to the caller
+        match MtLoggerBuilder::new("TEST_INIT_ERROR")
+            .log_dir("Cargo.toml")
+            .try_build()
+        {
+            Err(MtLoggerError::Io(_)) => (),
+            other => panic!("Expected MtLoggerError::Io, got {:?}", other.map(|_| ())),
+        }
 
-        // Verify that the verification files contain well-formatted messages
-        format_verf_helper(VerfFile::StdOut, verf_string_stdout, first_line_num)?;
-        format_verf_helper(VerfFile::FileOut, verf_string_file_out, first_line_num)?;
+        // Initializing the global instance a second time must be reported to the caller
+        if INSTANCE.get().is_none() {
+            try_mt_new!(LOGFILE_PREFIX, Level::Info, OutputStream::StdOut)?;
+        }
+        match try_mt_new!(LOGFILE_PREFIX, Level::Info, OutputStream::StdOut) {
+            Err(MtLoggerError::AlreadyInitialized) => (),
+            other => panic!(
+                "Expected MtLoggerError::AlreadyInitialized, got {:?}",
+                other
+            ),
+        }
 
         Ok(())
     }
 
-    fn outputstream_verf_helper(verf_type: VerfFile, verf_string: String) -> TestResult {
-        // Set up the verification items
-        const VERF_MATRIX: [[[&str; 2]; 4]; 2] = [
-            [
-                ["TRACE", "BOTH"],
-                ["FATAL", "BOTH"],
-                ["TRACE", "STDOUT"],
-                ["FATAL", "STDOUT"],
-            ],
-            [
-                ["TRACE", "BOTH"],
-                ["FATAL", "BOTH"],
-                ["TRACE", "FILEOUT"],
-                ["FATAL", "FILEOUT"],
-            ],
-        ];
-        const STDOUT_TYPE_IDX: usize = 0;
-        const FILE_OUT_TYPE_IDX: usize = 1;
-        const LEVEL_VERF_IDX: usize = 0;
-        const OUTPUT_TYPE_VERF_IDX: usize = 1;
+    #[test]
+    fn replace_test() -> TestResult {
+        // Lock logger mutex, as the replacement receiver will reset the verification files
+        let _mutex = LOGGER_MUTEX.lock()?;
 
-        // Set up output-specific parameters
-        let verf_type_idx;
-        let padless_level_hdr_capture_idx;
-        let header_regex;
-        match verf_type {
-            VerfFile::StdOut => {
-                verf_type_idx = STDOUT_TYPE_IDX;
-                padless_level_hdr_capture_idx = STDOUT_PADLESS_LEVEL_IDX;
-                header_regex = Regex::new(STDOUT_HDR_REGEX_STR)?;
-            }
-            VerfFile::FileOut => {
-                verf_type_idx = FILE_OUT_TYPE_IDX;
-                padless_level_hdr_capture_idx = FILE_OUT_PADLESS_LEVEL_IDX;
-                header_regex = Regex::new(FILE_OUT_HDR_REGEX_STR)?;
-            }
+        // Make sure there is an instance to replace
+        if INSTANCE.get().is_none() {
+            mt_new!(LOGFILE_PREFIX, Level::Info, OutputStream::StdOut);
         }
-        let output_type_capture_idx = 1;
+        mt_log!(
+            Level::Fatal,
+            "This message is recorded by the previous instance."
+        );
 
-        // Create regex for message content
-        let content_regex = Regex::new(r"^\s*This message appears in (\w*).")?;
+        // Replace the instance, which must flush the previous one and start a fresh count
+        mt_replace!(LOGFILE_PREFIX, Level::Info, OutputStream::StdOut)?;
+        assert_eq!(0, mt_count!());
 
-        // Read verf string into iterator
-        let mut verf_lines: Vec<&str> = verf_string.split('\n').collect();
-        let mut verf_line_iter = verf_lines.iter_mut();
+        mt_log!(Level::Info, "This message is recorded by the new instance.");
+        mt_flush!()?;
+        assert_eq!(1, mt_count!());
 
-        // Verify that the verification files contain the correct filter level and content lines
-        let mut i = 0;
-        while let Some(header_line) = verf_line_iter.next().filter(|v| !v.is_empty()) {
-            // Verify header contains the correct log level
-            let header_captures = header_regex.captures(header_line).unwrap_or_else(|| {
-                panic!(
-                    "{:?}: Header line {} '{}' did not match Regex:\n   {}",
-                    verf_type,
-                    i,
-                    header_line,
-                    header_regex.as_str()
-                )
-            });
-            if &header_captures[padless_level_hdr_capture_idx]
-                != VERF_MATRIX[verf_type_idx][i][LEVEL_VERF_IDX]
-            {
-                panic!(
-                    "{:?}: Wrong level '{}' on line '{}', should be '{}'",
-                    verf_type,
-                    &header_captures[padless_level_hdr_capture_idx],
-                    header_line,
-                    VERF_MATRIX[verf_type_idx][i][LEVEL_VERF_IDX]
-                );
-            }
+        Ok(())
+    }
 
-            // Verify content contains the correct output type
-            let content_line = verf_line_iter
-                .next()
-                .unwrap_or_else(|| panic!("Missing content line after header '{}'", header_line));
-            let content_captures = content_regex.captures(content_line).unwrap_or_else(|| {
-                panic!(
-                    "{:?}: Content line {} '{}' did not match content Regex:\n   {}",
-                    verf_type,
-                    i,
-                    content_line,
-                    content_regex.as_str()
-                )
-            });
+    #[test]
+    fn replace_reclaim_test() -> TestResult {
+        // Lock logger mutex, as the replacement receivers will reset the verification files
+        let _mutex = LOGGER_MUTEX.lock()?;
 
-            if &content_captures[output_type_capture_idx]
-                != VERF_MATRIX[verf_type_idx][i][OUTPUT_TYPE_VERF_IDX]
-            {
-                panic!(
-                    "{:?}: Wrong output type '{}' on line '{}', should be '{}'",
-                    verf_type,
-                    &content_captures[output_type_capture_idx],
-                    content_line,
-                    VERF_MATRIX[verf_type_idx][i][OUTPUT_TYPE_VERF_IDX]
-                )
+        // Make sure there is an instance to replace
+        if INSTANCE.get().is_none() {
+            mt_new!(LOGFILE_PREFIX, Level::Info, OutputStream::StdOut);
+        }
+
+        // Waits for a replaced instance to be freed, as other threads may briefly delay it
+        let wait_until_freed = |msg_count: &Weak<AtomicU64>| {
+            let deadline = time::Instant::now() + time::Duration::from_secs(5);
+            while msg_count.upgrade().is_some() && time::Instant::now() < deadline {
+                crate::instance::reclaim();
+                thread::sleep(time::Duration::from_millis(10));
             }
+            msg_count.upgrade().is_none()
+        };
 
-            i += 1;
-        }
+        // A replaced instance is freed once nothing references it
+        let replaced_count = Arc::downgrade(&INSTANCE.get().unwrap().msg_count);
+        mt_replace!(LOGFILE_PREFIX, Level::Info, OutputStream::StdOut)?;
+        assert!(wait_until_freed(&replaced_count));
+
+        // But not while a reference to it is still held
+        let held_instance = INSTANCE.get().unwrap();
+        let held_count = Arc::downgrade(&held_instance.msg_count);
+        mt_replace!(LOGFILE_PREFIX, Level::Info, OutputStream::StdOut)?;
+        assert!(held_count.upgrade().is_some());
+        assert!(log_line(
+            &held_instance,
+            Level::Info,
+            "Rejected by the replaced instance"
+        )
+        .is_err());
+        drop(held_instance);
+        assert!(wait_until_freed(&held_count));
 
         Ok(())
     }
 
     #[test]
-    fn outputstream_verification() -> TestResult {
-        // Lock logger mutex and hold it until we're done processing messages
-        let mutex = LOGGER_MUTEX.lock()?;
-
-        // Clean verification files before test
-        reset_verf_files()?;
+    fn sender_filter_test() -> TestResult {
+        // Lock logger mutex and hold it for the remainder of this test
+        let _mutex = LOGGER_MUTEX.lock()?;
 
-        // Create or update logger instance such that all messages are logged to Both outputs
+        // Set up the logger instance
         if INSTANCE.get().is_none() {
-            mt_new!(LOGFILE_PREFIX, Level::Trace, OutputStream::Both);
+            mt_new!(LOGFILE_PREFIX, Level::Warning, OutputStream::StdOut);
         } else {
-            mt_level!(Level::Trace);
-            mt_stream!(OutputStream::Both);
+            mt_level!(Level::Warning);
+            mt_stream!(OutputStream::StdOut);
         }
 
-        mt_log!(Level::Trace, "This message appears in BOTH.");
-        mt_log!(Level::Fatal, "This message appears in BOTH.");
+        // Filtering must be applied on the sending side as soon as the level is set
+        let instance = INSTANCE.get().unwrap();
+        assert!(!instance.is_enabled(Level::Info));
+        assert!(instance.is_enabled(Level::Warning));
+        instance.log_cmd(Command::SetOutputLevel(Level::Trace))?;
+        assert!(instance.is_enabled(Level::Trace));
+        mt_level!(Level::Warning);
+
+        // Arguments of filtered messages must never be evaluated
+        let mut args_evaluated = false;
+        mt_log!(Level::Info, "{}", {
+            args_evaluated = true;
+            "This message is filtered."
+        });
+        assert!(!args_evaluated);
+
+        mt_log!(Level::Error, "{}", {
+            args_evaluated = true;
+            "This message is recorded."
+        });
+        assert!(args_evaluated);
 
-        // Log messages to STDOUT only
-        mt_stream!(OutputStream::StdOut);
-        mt_log!(Level::Trace, "This message appears in STDOUT.");
-        mt_log!(Level::Fatal, "This message appears in STDOUT.");
+        mt_flush!()?;
 
-        // Log messages to FILE only
-        mt_stream!(OutputStream::File);
-        mt_log!(Level::Trace, "This message appears in FILEOUT.");
-        mt_log!(Level::Fatal, "This message appears in FILEOUT.");
+        Ok(())
+    }
 
-        // Log messages to NEITHER output
-        mt_stream!(OutputStream::Neither);
-        mt_log!(Level::Trace, "This message appears in NEITHER.");
-        mt_log!(Level::Fatal, "This message appears in NEITHER.");
+    // Sink announcing each message it starts recording, then holding up the logging thread until
+    // its gate is opened or dropped
+    struct BlockingSink {
+        entered: std::sync::mpsc::Sender<()>,
+        gate: std::sync::mpsc::Receiver<()>,
+    }
 
-        // Flush the messages to their output
-        println!("Flushing all messages to their output...");
-        let start_time = time::Instant::now();
-        mt_flush!()?;
-        println!("Done flushing after {}ms", start_time.elapsed().as_millis());
+    impl Sink for BlockingSink {
+        fn record(&mut self, _log_tuple: &MsgTuple) -> std::io::Result<()> {
+            let _ = self.entered.send(());
+            let _ = self.gate.recv();
+            Ok(())
+        }
+    }
 
-        // Capture the files in memory before releasing the mutex
-        let mut verf_file_stdout = fs::OpenOptions::new().read(true).open(STDOUT_FILENAME)?;
-        let mut verf_string_stdout = String::new();
-        verf_file_stdout.read_to_string(&mut verf_string_stdout)?;
-        let mut verf_file_file_out = fs::OpenOptions::new().read(true).open(FILE_OUT_FILENAME)?;
-        let mut verf_string_file_out = String::new();
-        verf_file_file_out.read_to_string(&mut verf_string_file_out)?;
+    #[test]
+    fn filter_update_test() -> TestResult {
+        // Lock logger mutex, as the new receiver will reset the verification files
+        let _mutex = LOGGER_MUTEX.lock()?;
 
-        // Unlock the mutex
-        std::mem::drop(mutex);
+        let (entered_tx, entered_rx) = std::sync::mpsc::channel();
+        let (gate_tx, gate_rx) = std::sync::mpsc::channel();
+        let logger = MtLoggerBuilder::new("TEST_FILTER_UPDATE")
+            .output_level(Level::Info)
+            .output_stream(OutputStream::Neither)
+            .channel_capacity(1)
+            .sink(
+                SinkId::unique(),
+                BlockingSink {
+                    entered: entered_tx,
+                    gate: gate_rx,
+                },
+            )
+            .build();
+
+        // Fill a producer's queue while the logging thread is held up, so its level update blocks
+        let (go_tx, go_rx) = std::sync::mpsc::channel();
+        let producer = thread::spawn({
+            let logger = logger.clone();
+            move || -> Result<(), SendError<Command>> {
+                log_line(&logger, Level::Info, "Being recorded")?;
+                let _ = go_rx.recv();
+                log_line(&logger, Level::Info, "Filling the queue")?;
+                logger.log_cmd(Command::SetOutputLevel(Level::Trace))
+            }
+        });
+        entered_rx.recv()?;
+        go_tx.send(())?;
+        thread::sleep(time::Duration::from_millis(100));
 
-        // Verify that the verification files contain only the correct messages
-        outputstream_verf_helper(VerfFile::StdOut, verf_string_stdout)?;
-        outputstream_verf_helper(VerfFile::FileOut, verf_string_file_out)?;
+        // The update applies to the sending side right away, without holding up other updates
+        assert!(logger.is_enabled(Level::Trace));
+        assert!(logger.target_filter.try_lock().is_ok());
+        logger.log_cmd(Command::SetTargetLevel("app".to_string(), Level::Debug))?;
+
+        drop(gate_tx);
+        producer.join().map_err(|_| "Producer thread panicked")??;
+        logger.shutdown()?;
 
         Ok(())
     }
 
-    #[test]
-    fn flush_test() -> TestResult {
-        // Lock logger mutex and hold it for the remainder of this test
-        let _mutex = LOGGER_MUTEX.lock()?;
-
-        // Clean verification files before test
-        reset_verf_files()?;
+    // Creates a log message command whose content is the given index
+    fn indexed_msg(idx: u32) -> Command {
+        Command::LogMsg(Box::new(MsgTuple {
+            line: idx,
+            ..msg_tuple(Level::Info, idx.to_string())
+        }))
+    }
 
-        // Set up the logger instance
-        if INSTANCE.get().is_none() {
-            mt_new!(LOGFILE_PREFIX, Level::Info, OutputStream::StdOut);
-        } else {
-            mt_level!(Level::Info);
-            mt_stream!(OutputStream::StdOut);
+    // Receives every queued message, returning their indices
+    fn received_indices(logger_rx: &channel::LoggerRx) -> Vec<u32> {
+        let mut indices = Vec::new();
+        while let Ok(Command::LogMsg(log_tuple)) = logger_rx.try_recv() {
+            indices.push(log_tuple.line);
         }
 
-        // Capture the initial count
-        let initial_msg_count = mt_count!();
-        eprintln!("Initial message count: {}", initial_msg_count);
+        indices
+    }
 
-        // Send some messages
-        eprintln!("Sending messages...");
-        let sent_msg_count = 5;
-        for i in 0..sent_msg_count {
-            mt_log!(Level::Info, "Message #{}", i);
+    #[test]
+    fn overflow_policy_test() -> TestResult {
+        // Drop the message being sent when full
+        let (logger_tx, logger_rx) = channel::channel(2, OverflowPolicy::DropNewest);
+        for i in 0..3 {
+            logger_tx.send(indexed_msg(i))?;
+        }
+        assert_eq!(vec![0, 1], received_indices(&logger_rx));
+        assert_eq!(1, logger_rx.take_dropped_count());
+        assert_eq!(0, logger_rx.take_dropped_count());
+
+        // Drop the oldest queued message when full
+        let (logger_tx, logger_rx) = channel::channel(2, OverflowPolicy::DropOldest);
+        for i in 0..3 {
+            logger_tx.send(indexed_msg(i))?;
         }
+        assert_eq!(vec![1, 2], received_indices(&logger_rx));
+        assert_eq!(1, logger_rx.take_dropped_count());
+
+        // Never drop commands, instead dropping the message being sent if a command is oldest
+        let (logger_tx, logger_rx) = channel::channel(2, OverflowPolicy::DropOldest);
+        let (flush_ack_tx, _flush_ack_rx) = std::sync::mpsc::channel();
+        logger_tx.send(Command::Flush(flush_ack_tx))?;
+        for i in 0..3 {
+            logger_tx.send(indexed_msg(i))?;
+        }
+        assert!(matches!(logger_rx.try_recv(), Ok(Command::Flush(_))));
+        assert_eq!(vec![0], received_indices(&logger_rx));
+        assert_eq!(2, logger_rx.take_dropped_count());
+
+        // Give up on the message being sent once the timeout elapses
+        let timeout = time::Duration::from_millis(50);
+        let (logger_tx, logger_rx) = channel::channel(1, OverflowPolicy::BlockTimeout(timeout));
+        logger_tx.send(indexed_msg(0))?;
+        let start_time = time::Instant::now();
+        logger_tx.send(indexed_msg(1))?;
+        assert!(start_time.elapsed() >= timeout);
+        assert_eq!(vec![0], received_indices(&logger_rx));
+        assert_eq!(1, logger_rx.take_dropped_count());
+
+        // Block until the receiver makes room
+        let (logger_tx, logger_rx) = channel::channel(1, OverflowPolicy::Block);
+        logger_tx.send(indexed_msg(0))?;
+        let receiver_thread = std::thread::spawn(move || {
+            std::thread::sleep(time::Duration::from_millis(50));
+            let mut indices = Vec::new();
+            while let Ok(Command::LogMsg(log_tuple)) = logger_rx.recv() {
+                indices.push(log_tuple.line);
+            }
+            (indices, logger_rx.take_dropped_count())
+        });
+        logger_tx.send(indexed_msg(1))?;
+        std::mem::drop(logger_tx);
+        assert_eq!((vec![0, 1], 0), receiver_thread.join().unwrap());
 
-        // Send a flush command
-        mt_flush!()?;
+        Ok(())
+    }
 
-        // Verify that all sent messages were processed after flushing
-        eprintln!("Messages processed: {}", mt_count!());
-        assert_eq!(initial_msg_count + sent_msg_count, mt_count!());
+    #[test]
+    fn send_order_test() -> TestResult {
+        // Messages from different threads are received in the order they were sent, even if the
+        // wall clock stepped backwards in between
+        let (logger_tx, logger_rx) = channel::channel(16, OverflowPolicy::Block);
+        let future_tx = logger_tx.clone();
+        std::thread::spawn(move || {
+            let mut future_msg = indexed_msg(0);
+            if let Command::LogMsg(log_tuple) = &mut future_msg {
+                log_tuple.timestamp += chrono::Duration::hours(1);
+            }
+            future_tx.send(future_msg)
+        })
+        .join()
+        .unwrap()?;
+        logger_tx.send(indexed_msg(1))?;
+
+        assert_eq!(vec![0, 1], received_indices(&logger_rx));
+
+        Ok(())
+    }
+
+    #[test]
+    fn multi_producer_test() -> TestResult {
+        const PRODUCER_COUNT: u32 = 8;
+        const MSGS_PER_PRODUCER: u32 = 1000;
+
+        // Each producer thread gets its own ring, much smaller than the number of messages sent
+        let (logger_tx, logger_rx) = channel::channel(16, OverflowPolicy::Block);
+        let producers: Vec<_> = (0..PRODUCER_COUNT)
+            .map(|producer_idx| {
+                let logger_tx = logger_tx.clone();
+                std::thread::spawn(move || {
+                    for i in 0..MSGS_PER_PRODUCER {
+                        logger_tx
+                            .send(indexed_msg(producer_idx * MSGS_PER_PRODUCER + i))
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        std::mem::drop(logger_tx);
+
+        // Every message must arrive exactly once, in order for any given producer
+        let mut last_received = vec![None; PRODUCER_COUNT as usize];
+        let mut received_count = 0;
+        while let Ok(cmd) = logger_rx.recv() {
+            if let Command::LogMsg(log_tuple) = cmd {
+                let producer_idx = (log_tuple.line / MSGS_PER_PRODUCER) as usize;
+                assert!(last_received[producer_idx] < Some(log_tuple.line));
+                last_received[producer_idx] = Some(log_tuple.line);
+                received_count += 1;
+            }
+        }
+        assert_eq!(PRODUCER_COUNT * MSGS_PER_PRODUCER, received_count);
+
+        for producer in producers {
+            producer.join().unwrap();
+        }
 
         Ok(())
     }
@@ -0,0 +1,809 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : logfile.rs
+
+Copyright (C) 2021 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module defines the Logfile Naming scheme, which expands the log
+    directory and logfile name templates into the path of a logfile and keeps
+    the link to the active logfile up to date, and the
+    File Rotation state used by the file sink to replace the active logfile
+    once it grows too large or a new rotation period begins, and the Retention
+    policy deleting old logfiles matching the naming scheme.
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use std::cmp::Reverse;
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, Local, Offset};
+
+#[cfg(feature = "gzip")]
+use crate::compress::{self, Compressor};
+use crate::{MtLoggerBuilder, RotationInterval, RotationStyle};
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Named Constants
+///////////////////////////////////////////////////////////////////////////////
+
+/// Format string for logfile names. Conforms to ISO 8601, except : has been replaced with _ to make Windows happy.
+const FILE_TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H_%M_%S%.3f%z";
+
+/// Format string for the `{date}` placeholder
+const FILE_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Shape of a timestamp formatted with `FILE_TIMESTAMP_FORMAT`, where `#` stands for an ASCII
+/// digit and `±` for the sign of the UTC offset
+const FILE_TIMESTAMP_SHAPE: &str = "####-##-##T##_##_##.###±####";
+
+/// Shape of a date formatted with `FILE_DATE_FORMAT`, see `FILE_TIMESTAMP_SHAPE`
+const FILE_DATE_SHAPE: &str = "####-##-##";
+
+/// Host name used if the actual name cannot be determined
+const UNKNOWN_HOSTNAME: &str = "unknown-host";
+
+/// Seconds per hour, the length of a `RotationInterval::Hourly` period
+const SECS_PER_HOUR: i64 = 60 * 60;
+
+/// Seconds per day, the length of a `RotationInterval::Daily` period
+const SECS_PER_DAY: i64 = 24 * SECS_PER_HOUR;
+
+/// Seconds per week, the length of a `RotationInterval::Weekly` period
+const SECS_PER_WEEK: i64 = 7 * SECS_PER_DAY;
+
+/// Offset of the first Monday after January 1st, 1970, from which weeks are counted
+const FIRST_MONDAY_OFFSET: i64 = 4 * SECS_PER_DAY;
+
+/// Extension appended to the names of compressed logfiles
+pub const GZIP_EXTENSION: &str = ".gz";
+
+/// Placeholders whose expansion differs between runs, matched by shape when searching for logfiles
+const VARIABLE_PLACEHOLDERS: [(&str, Placeholder); 3] = [
+    ("{timestamp}", Placeholder::Timestamp),
+    ("{date}", Placeholder::Date),
+    ("{pid}", Placeholder::Pid),
+];
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+/// Templates determining where logfiles are created and how they are named
+#[derive(Clone, Debug)]
+pub struct LogfileNaming {
+    log_dir: PathBuf,
+    run_dir_template: Option<String>,
+    file_name_template: String,
+    logfile_prefix: String,
+    hostname: String,
+    append: bool,
+    latest_link: Option<String>,
+}
+
+/// Piece of a template, as matched against existing file and directory names
+#[derive(Clone, Debug, PartialEq)]
+enum TemplateToken {
+    Literal(String),
+    Variable(Placeholder),
+}
+
+/// Placeholder whose expansion differs between runs
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Placeholder {
+    Timestamp,
+    Date,
+    Pid,
+}
+
+/// Limits on the logfiles kept in the log directory
+#[derive(Clone, Debug)]
+pub struct Retention {
+    naming: LogfileNaming,
+    max_files: Option<usize>,
+    max_age: Option<Duration>,
+    max_total_size: Option<u64>,
+}
+
+/// Rotation settings and state of the active logfile
+#[derive(Debug)]
+pub struct FileRotation {
+    naming: LogfileNaming,
+    retention: Option<Retention>,
+    #[cfg(feature = "gzip")]
+    compressor: Option<Compressor>,
+    #[cfg(feature = "gzip")]
+    pending_count: u64,
+    path: PathBuf,
+    style: RotationStyle,
+    max_size: Option<u64>,
+    file_size: u64,
+    interval: Option<RotationInterval>,
+    utc: bool,
+    period: i64,
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Object Implementation
+///////////////////////////////////////////////////////////////////////////////
+
+impl LogfileNaming {
+    /// Constructs the naming scheme configured by the builder
+    pub fn new(builder: &MtLoggerBuilder) -> Self {
+        Self {
+            log_dir: builder.log_dir.clone(),
+            run_dir_template: builder.run_dir_template.clone(),
+            file_name_template: builder.file_name_template.clone(),
+            logfile_prefix: builder.logfile_prefix.clone(),
+            hostname: hostname(),
+            append: builder.append,
+            latest_link: builder.latest_link.clone(),
+        }
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Utility Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Returns the path of the logfile for a run started at the given time
+    pub fn path_for(&self, start_time: DateTime<Local>) -> PathBuf {
+        let mut path_buf = self.log_dir.clone();
+        if let Some(run_dir_template) = &self.run_dir_template {
+            path_buf.push(self.expand(run_dir_template, start_time));
+        }
+        path_buf.push(self.expand(&self.file_name_template, start_time));
+
+        path_buf
+    }
+
+    /// Returns every existing logfile that could have been created with this naming scheme by
+    /// any run, including rotated logfiles
+    pub fn find_logfiles(&self) -> io::Result<Vec<PathBuf>> {
+        let mut dir_patterns = Vec::new();
+        if let Some(run_dir_template) = &self.run_dir_template {
+            for component in run_dir_template.split(['/', '\\']) {
+                if !component.is_empty() {
+                    dir_patterns.push(self.tokenize(component));
+                }
+            }
+        }
+        let file_pattern = self.tokenize(&self.file_name_template);
+
+        let mut logfiles = Vec::new();
+        find_matching_files(&self.log_dir, &dir_patterns, &file_pattern, &mut logfiles)?;
+
+        Ok(logfiles)
+    }
+
+    /// Creates the logfile for a run started at the given time, along with any missing
+    /// directories, returning its path along with the file
+    pub fn create(&self, start_time: DateTime<Local>) -> io::Result<(PathBuf, File)> {
+        let path_buf = self.path_for(start_time);
+        let logfile = self.open(&path_buf)?;
+
+        Ok((path_buf, logfile))
+    }
+
+    /// Opens the logfile at the given path for writing, along with any missing directories.
+    /// Existing logfiles are appended to in append mode, and truncated otherwise.
+    pub fn open(&self, path: &Path) -> io::Result<File> {
+        open_logfile(path, self.append)
+    }
+
+    /// Points the link to the latest logfile at the given logfile, if enabled.
+    ///
+    /// The link is replaced atomically, so that it is never missing for tools following it.
+    pub fn update_latest_link(&self, active_path: &Path) -> io::Result<()> {
+        let link_name = match &self.latest_link {
+            Some(link_name) => link_name,
+            None => return Ok(()),
+        };
+
+        // Point at the logfile relative to the log directory, so that the link survives moving it
+        let target = active_path
+            .strip_prefix(&self.log_dir)
+            .unwrap_or(active_path);
+        let partial_path = self.log_dir.join(format!("{}.tmp", link_name));
+        match fs::remove_file(&partial_path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+            _ => (),
+        }
+        create_symlink(target, &partial_path)?;
+
+        fs::rename(&partial_path, self.log_dir.join(link_name))
+    }
+
+
+    /*  *  *  *  *  *  *\
+     * Helper Methods *
+    \*  *  *  *  *  *  */
+
+    /// Replaces every placeholder in the template
+    fn expand(&self, template: &str, start_time: DateTime<Local>) -> String {
+        template
+            .replace("{prefix}", &self.logfile_prefix)
+            .replace(
+                "{timestamp}",
+                &start_time.format(FILE_TIMESTAMP_FORMAT).to_string(),
+            )
+            .replace("{date}", &start_time.format(FILE_DATE_FORMAT).to_string())
+            .replace("{pid}", &process::id().to_string())
+            .replace("{hostname}", &self.hostname)
+    }
+
+    /// Removes the run directories containing a deleted logfile, if they are now empty
+    fn remove_empty_run_dirs(&self, deleted_path: &Path) {
+        let mut dir = deleted_path.parent();
+        while let Some(run_dir) = dir.filter(|run_dir| *run_dir != self.log_dir) {
+            // Fails if the directory still contains other files, which is fine
+            if fs::remove_dir(run_dir).is_err() {
+                break;
+            }
+            dir = run_dir.parent();
+        }
+    }
+
+    /// Splits the template into literal text and placeholders that vary between runs
+    fn tokenize(&self, template: &str) -> Vec<TemplateToken> {
+        let mut tokens = Vec::new();
+        let mut literal = String::new();
+        let mut remainder = template;
+        while let Some(c) = remainder.chars().next() {
+            match VARIABLE_PLACEHOLDERS
+                .iter()
+                .find(|(text, _)| remainder.starts_with(text))
+            {
+                Some((text, placeholder)) => {
+                    if !literal.is_empty() {
+                        tokens.push(TemplateToken::Literal(literal.split_off(0)));
+                    }
+                    tokens.push(TemplateToken::Variable(*placeholder));
+                    remainder = &remainder[text.len()..];
+                }
+                None => {
+                    literal.push(c);
+                    remainder = &remainder[c.len_utf8()..];
+                }
+            }
+        }
+        if !literal.is_empty() {
+            tokens.push(TemplateToken::Literal(literal));
+        }
+
+        // The prefix and host name are the same for every run
+        tokens
+            .into_iter()
+            .map(|token| match token {
+                TemplateToken::Literal(literal) => TemplateToken::Literal(
+                    literal
+                        .replace("{prefix}", &self.logfile_prefix)
+                        .replace("{hostname}", &self.hostname),
+                ),
+                variable => variable,
+            })
+            .collect()
+    }
+}
+
+impl Retention {
+    /// Constructs the retention policy configured by the builder, if any limits are set
+    pub fn new(builder: &MtLoggerBuilder, naming: LogfileNaming) -> Option<Self> {
+        if builder.max_logfiles.is_none()
+            && builder.max_logfile_age.is_none()
+            && builder.max_total_logfile_size.is_none()
+        {
+            return None;
+        }
+
+        Some(Self {
+            naming,
+            max_files: builder.max_logfiles,
+            max_age: builder.max_logfile_age,
+            max_total_size: builder.max_total_logfile_size,
+        })
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Utility Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Deletes the oldest logfiles matching the naming scheme until every limit is met, never
+    /// deleting the active logfile.
+    ///
+    /// Continues past logfiles that cannot be inspected or deleted, returning the first error
+    /// encountered. Logfiles that disappear in the meantime, e.g., while being compressed in the
+    /// background, are skipped.
+    pub fn enforce(&self, active_path: &Path) -> io::Result<()> {
+        let now = SystemTime::now();
+        let mut first_err = None;
+
+        // Collect the size and age of every inactive logfile, newest first
+        let mut logfiles = Vec::new();
+        for path_buf in self.naming.find_logfiles()? {
+            if path_buf != active_path {
+                match fs::metadata(&path_buf) {
+                    Ok(metadata) => {
+                        let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                        logfiles.push((modified, metadata.len(), path_buf));
+                    }
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+                    Err(err) => {
+                        first_err.get_or_insert(err);
+                    }
+                }
+            }
+        }
+        logfiles.sort_by_key(|logfile| Reverse(logfile.0));
+
+        // Keep the newest logfiles within the age limit, counting the active logfile as kept
+        let max_kept = self
+            .max_files
+            .map_or(usize::MAX, |max_files| max_files.saturating_sub(1));
+        let mut kept = Vec::new();
+        let mut expired = Vec::new();
+        for logfile in logfiles {
+            let too_old = match self.max_age {
+                Some(max_age) => now.duration_since(logfile.0).unwrap_or_default() > max_age,
+                None => false,
+            };
+            if too_old || kept.len() >= max_kept {
+                expired.push(logfile);
+            } else {
+                kept.push(logfile);
+            }
+        }
+
+        // Then expire the oldest logfiles kept until the total size limit is met
+        if let Some(max_total_size) = self.max_total_size {
+            let mut total_size = fs::metadata(active_path).map_or(0, |metadata| metadata.len())
+                + kept.iter().map(|logfile| logfile.1).sum::<u64>();
+            while total_size > max_total_size {
+                match kept.pop() {
+                    Some(logfile) => {
+                        total_size -= logfile.1;
+                        expired.push(logfile);
+                    }
+                    None => break,
+                }
+            }
+        }
+
+        for (_, _, path_buf) in expired {
+            match fs::remove_file(&path_buf) {
+                Ok(()) => self.naming.remove_empty_run_dirs(&path_buf),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+                Err(err) => {
+                    first_err.get_or_insert(err);
+                }
+            }
+        }
+
+        first_err.map_or(Ok(()), Err)
+    }
+}
+
+impl FileRotation {
+    /// Constructs the rotation state configured by the builder for the logfile at `path`, newly
+    /// created at `start_time`
+    pub fn new(
+        builder: &MtLoggerBuilder,
+        naming: LogfileNaming,
+        path: PathBuf,
+        start_time: DateTime<Local>,
+    ) -> io::Result<Self> {
+        let mut rotation = Self {
+            retention: Retention::new(builder, naming.clone()),
+            #[cfg(feature = "gzip")]
+            compressor: if builder.compress_rotated {
+                Some(Compressor::new(
+                    format!("{}_gzip", builder.thread_name),
+                    builder.timestamp_format.clone(),
+                )?)
+            } else {
+                None
+            },
+            #[cfg(feature = "gzip")]
+            pending_count: 0,
+            naming,
+            path,
+            style: builder.rotation_style,
+            max_size: builder.rotation_size,
+            file_size: 0,
+            interval: builder.rotation_interval,
+            utc: builder.rotation_utc,
+            period: 0,
+        };
+        rotation.period = rotation.period_of(start_time);
+
+        // Logfiles reopened in append mode count towards the size limit from the start
+        rotation.file_size = fs::metadata(&rotation.path).map_or(0, |metadata| metadata.len());
+
+        Ok(rotation)
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Accessor Methods  *
+    \*  *  *  *  *  *  *  */
+
+    /// Checks whether the active logfile must be rotated before writing a message of the given
+    /// length and timestamp, so that messages are never split across logfiles
+    pub fn is_due(&self, msg_len: u64, timestamp: DateTime<Local>) -> bool {
+        let size_exceeded = match self.max_size {
+            Some(max_size) => self.file_size > 0 && self.file_size + msg_len > max_size,
+            None => false,
+        };
+
+        // Messages from a previous period, e.g., logged just before a boundary by another thread,
+        // are written to the active logfile
+        size_exceeded || self.period_of(timestamp) > self.period
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Mutator Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Accounts for a message of the given length written to the active logfile
+    pub fn add_written(&mut self, msg_len: u64) {
+        self.file_size += msg_len;
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Utility Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Moves the active logfile out of the way according to the rotation style, returning a newly
+    /// created logfile to continue writing to, starting with a message of the given timestamp.
+    ///
+    /// The active logfile must have been flushed beforehand.
+    pub fn rotate(&mut self, rotation_time: DateTime<Local>) -> io::Result<File> {
+        // Timestamped rotation falls back to indexing if the name would not change
+        let timestamped_path = match self.style {
+            RotationStyle::Timestamped => Some(self.naming.path_for(rotation_time))
+                .filter(|path_buf| path_buf != &self.path && !path_buf.exists()),
+            RotationStyle::Indexed => None,
+        };
+
+        match timestamped_path {
+            Some(path_buf) => {
+                let rotated_path = mem::replace(&mut self.path, path_buf);
+                self.retire(rotated_path, false)?;
+            }
+            None => self.retire(self.path.clone(), true)?,
+        }
+
+        let logfile = self.naming.open(&self.path)?;
+        self.file_size = 0;
+        self.period = self.period.max(self.period_of(rotation_time));
+
+        Ok(logfile)
+    }
+
+    /// Reopens the active logfile at its path, appending to it if it still exists, e.g., after
+    /// it was renamed by an external tool
+    pub fn reopen(&mut self) -> io::Result<File> {
+        let logfile = open_logfile(&self.path, true)?;
+        self.file_size = logfile.metadata()?.len();
+
+        Ok(logfile)
+    }
+
+    /// Points the link to the latest logfile at the active logfile, if enabled
+    pub fn update_latest_link(&self) -> io::Result<()> {
+        self.naming.update_latest_link(&self.path)
+    }
+
+    /// Deletes old logfiles according to the retention policy, if any
+    pub fn enforce_retention(&self) -> io::Result<()> {
+        match &self.retention {
+            Some(retention) => retention.enforce(&self.path),
+            None => Ok(()),
+        }
+    }
+
+
+    /*  *  *  *  *  *  *\
+     * Helper Methods *
+    \*  *  *  *  *  *  */
+
+    /// Moves the rotated logfile at `path` to its final name, compressing it in the background if
+    /// enabled. Indexed logfiles are renamed to `<path>.1`, after shifting the indices of older
+    /// logfiles.
+    fn retire(&mut self, path: PathBuf, indexed: bool) -> io::Result<()> {
+        #[cfg(feature = "gzip")]
+        if let Some(compressor) = &self.compressor {
+            // Indices are shifted by the compression thread, so that jobs never race over
+            // names. Until then, the logfile is set aside under a name no other logfile can have.
+            if indexed {
+                self.pending_count += 1;
+                let mut pending_name = path.file_name().map_or_else(OsString::new, OsString::from);
+                pending_name.push(format!(".pending-{}", self.pending_count));
+                let pending_path = path.with_file_name(pending_name);
+                fs::rename(&path, &pending_path)?;
+
+                compressor.submit(
+                    path.clone(),
+                    Box::new(move || {
+                        shift_indexed_logfiles(&path)?;
+                        compress::gzip_file(
+                            &pending_path,
+                            &compress::gzip_path(&indexed_path(&path, 1)),
+                        )
+                    }),
+                );
+            } else {
+                compressor.submit(
+                    path.clone(),
+                    Box::new(move || compress::gzip_file(&path, &compress::gzip_path(&path))),
+                );
+            }
+            return Ok(());
+        }
+
+        if indexed {
+            shift_indexed_logfiles(&path)?;
+            fs::rename(&path, indexed_path(&path, 1))?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the index of the rotation period containing the given time, counted from
+    /// January 1st, 1970 on local or UTC boundaries
+    fn period_of(&self, timestamp: DateTime<Local>) -> i64 {
+        let mut secs = timestamp.timestamp();
+        if !self.utc {
+            secs += i64::from(timestamp.offset().fix().local_minus_utc());
+        }
+
+        match self.interval {
+            Some(RotationInterval::Hourly) => secs.div_euclid(SECS_PER_HOUR),
+            Some(RotationInterval::Daily) => secs.div_euclid(SECS_PER_DAY),
+            Some(RotationInterval::Weekly) => {
+                (secs - FIRST_MONDAY_OFFSET).div_euclid(SECS_PER_WEEK)
+            }
+            Some(RotationInterval::Custom(interval)) => {
+                secs.div_euclid(interval.as_secs().clamp(1, i64::MAX as u64) as i64)
+            }
+            None => 0,
+        }
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Static Functions
+///////////////////////////////////////////////////////////////////////////////
+
+/// Returns the path of the logfile at `path` rotated with the given index, e.g., `app.log.2`
+fn indexed_path(path: &Path, index: u32) -> PathBuf {
+    let mut file_name = path.file_name().map_or_else(OsString::new, OsString::from);
+    file_name.push(format!(".{}", index));
+
+    path.with_file_name(file_name)
+}
+
+/// Increments the index of every logfile previously rotated from `path`, compressed or not, so
+/// that `<path>.1` is free
+fn shift_indexed_logfiles(path: &Path) -> io::Result<()> {
+    let compressed_path = |index| {
+        let mut file_name = indexed_path(path, index).into_os_string();
+        file_name.push(GZIP_EXTENSION);
+        PathBuf::from(file_name)
+    };
+
+    let mut last_index = 0;
+    while indexed_path(path, last_index + 1).exists() || compressed_path(last_index + 1).exists() {
+        last_index += 1;
+    }
+
+    // Rename from the highest index down, so that no logfile is overwritten. Logfiles may be
+    // deleted by the retention policy in the meantime, which is fine.
+    for index in (1..=last_index).rev() {
+        for (from, to) in &[
+            (indexed_path(path, index), indexed_path(path, index + 1)),
+            (compressed_path(index), compressed_path(index + 1)),
+        ] {
+            match fs::rename(from, to) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+                _ => (),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Collects every file under `dir` whose relative path matches the directory patterns followed by
+/// the file pattern, allowing for rotated and compressed logfiles
+fn find_matching_files(
+    dir: &Path,
+    dir_patterns: &[Vec<TemplateToken>],
+    file_pattern: &[TemplateToken],
+    matches: &mut Vec<PathBuf>,
+) -> io::Result<()> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+
+    for entry in entries {
+        let entry = entry?;
+        let file_type = match entry.file_type() {
+            Ok(file_type) => file_type,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        };
+        let name = entry.file_name();
+        let name = match name.to_str() {
+            Some(name) => name,
+            None => continue,
+        };
+
+        match dir_patterns.split_first() {
+            Some((dir_pattern, remaining_patterns)) => {
+                if file_type.is_dir() && matches_pattern(dir_pattern, name) {
+                    find_matching_files(&entry.path(), remaining_patterns, file_pattern, matches)?;
+                }
+            }
+            None => {
+                let name = name.strip_suffix(GZIP_EXTENSION).unwrap_or(name);
+                if file_type.is_file()
+                    && (matches_pattern(file_pattern, name)
+                        || matches_pattern(file_pattern, strip_rotation_suffix(name)))
+                {
+                    matches.push(entry.path());
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks whether the name could have been expanded from the tokenized template
+fn matches_pattern(pattern: &[TemplateToken], name: &str) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((TemplateToken::Literal(literal), remaining_pattern)) => name
+            .strip_prefix(literal.as_str())
+            .is_some_and(|remainder| matches_pattern(remaining_pattern, remainder)),
+        // Process IDs vary in length, so try every run of digits
+        Some((TemplateToken::Variable(Placeholder::Pid), remaining_pattern)) => {
+            let digit_count = name.bytes().take_while(u8::is_ascii_digit).count();
+            (1..=digit_count).any(|idx| matches_pattern(remaining_pattern, &name[idx..]))
+        }
+        Some((TemplateToken::Variable(Placeholder::Timestamp), remaining_pattern)) => {
+            strip_shape(FILE_TIMESTAMP_SHAPE, name)
+                .is_some_and(|remainder| matches_pattern(remaining_pattern, remainder))
+        }
+        Some((TemplateToken::Variable(Placeholder::Date), remaining_pattern)) => {
+            strip_shape(FILE_DATE_SHAPE, name)
+                .is_some_and(|remainder| matches_pattern(remaining_pattern, remainder))
+        }
+    }
+}
+
+/// Strips text of the given shape from the start of the name, returning the remainder
+fn strip_shape<'a>(shape: &str, name: &'a str) -> Option<&'a str> {
+    let mut name_chars = name.chars();
+    for shape_char in shape.chars() {
+        let name_char = name_chars.next()?;
+        let matches = match shape_char {
+            '#' => name_char.is_ascii_digit(),
+            '±' => name_char == '+' || name_char == '-',
+            _ => name_char == shape_char,
+        };
+        if !matches {
+            return None;
+        }
+    }
+
+    Some(name_chars.as_str())
+}
+
+/// Strips the index appended to the name of a rotated logfile, e.g., `.2` from `app.log.2`
+fn strip_rotation_suffix(name: &str) -> &str {
+    match name.rfind('.') {
+        Some(dot_idx)
+            if dot_idx + 1 < name.len()
+                && name[dot_idx + 1..]
+                    .bytes()
+                    .all(|byte| byte.is_ascii_digit()) =>
+        {
+            &name[..dot_idx]
+        }
+        _ => name,
+    }
+}
+
+/// Opens the logfile at the given path for writing, along with any missing directories.
+/// Existing logfiles are appended to if `append` is set, and truncated otherwise.
+pub fn open_logfile(path: &Path, append: bool) -> io::Result<File> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .append(append)
+        .truncate(!append)
+        .open(path)
+}
+
+/// Creates a symbolic link at `link` pointing at the file at `target`
+fn create_symlink(target: &Path, link: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    return std::os::unix::fs::symlink(target, link);
+
+    #[cfg(windows)]
+    return std::os::windows::fs::symlink_file(target, link);
+
+    #[cfg(not(any(unix, windows)))]
+    return Err(io::Error::new(
+        io::ErrorKind::Other,
+        format!(
+            "symbolic link to '{}' not supported on this platform",
+            target.display()
+        ),
+    ));
+}
+
+/// Returns the name of the host the process is running on
+#[cfg(unix)]
+fn hostname() -> String {
+    use std::os::raw::{c_char, c_int};
+
+    extern "C" {
+        fn gethostname(name: *mut c_char, len: usize) -> c_int;
+    }
+
+    let mut name_buf = [0u8; 256];
+    // SAFETY: The buffer is valid for writes of its full length, and gethostname() never writes
+    // past the given length.
+    let result = unsafe { gethostname(name_buf.as_mut_ptr() as *mut c_char, name_buf.len()) };
+    if result != 0 {
+        return UNKNOWN_HOSTNAME.to_string();
+    }
+
+    // The name may not be null-terminated if it was truncated
+    let name_len = name_buf
+        .iter()
+        .position(|&byte| byte == 0)
+        .unwrap_or(name_buf.len());
+    match String::from_utf8_lossy(&name_buf[..name_len]).into_owned() {
+        name if name.is_empty() => UNKNOWN_HOSTNAME.to_string(),
+        name => name,
+    }
+}
+
+/// Returns the name of the host the process is running on
+#[cfg(not(unix))]
+fn hostname() -> String {
+    std::env::var("COMPUTERNAME").unwrap_or_else(|_| UNKNOWN_HOSTNAME.to_string())
+}
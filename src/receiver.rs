@@ -21,43 +21,48 @@ Purpose:
 \* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
 
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::{mpsc, Arc};
+use std::sync::mpsc::{self, RecvTimeoutError, TryRecvError};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
 
-use std::fs::{self, File};
-use std::io::prelude::*;
-use std::path::PathBuf;
+use std::io::{self, Write};
+use std::process;
+#[cfg(test)]
+use std::{fs, path::PathBuf};
 
-use chrono::Local;
+use chrono::{DateTime, Local};
 
-use crate::{Command, Level, MsgTuple, OutputStream};
+use crate::channel::LoggerRx;
+use crate::filter::TargetFilter;
+use crate::logfile::{FileRotation, LogfileNaming, Retention};
+#[cfg(target_os = "linux")]
+use crate::signal::ReopenHandler;
+use crate::sink::{ConsoleSink, FileSink, RecordFormat, SinkEntry};
+#[cfg(test)]
+use crate::Sink;
+use crate::{
+    Command, DurabilityPolicy, Level, LevelMask, MsgTuple, MtLoggerBuilder, OutputStream, SinkId,
+};
 
 
 ///////////////////////////////////////////////////////////////////////////////
 //  Named Constants
 ///////////////////////////////////////////////////////////////////////////////
 
-/// Format string for logfile names. Conforms to ISO 8601, except : has been replaced with _ to make Windows happy.
-const FILE_TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H_%M_%S%.3f%z";
-
-/// Format string for timestamps
-#[cfg(not(test))]
-const ENTRY_TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%T%.9f";
-#[cfg(test)]
-pub const ENTRY_TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%T%.9f";
-
-/// Padding required to align text after Level label
-const LEVEL_LABEL_WIDTH: usize = 9;
-
-/// Padding to the left of the log message
-const MESSAGE_LEFT_PADDING: usize = 3;
+/// Line framing the header written at the start of each run in append mode
+const RUN_SEPARATOR: &str =
+    "================================================================================";
 
-/// Logfile directory location
-const LOGFILE_DIR: &str = "logs";
+/// Interval at which the number of messages dropped due to a full channel is reported
+const DROP_REPORT_INTERVAL: Duration = Duration::from_secs(1);
 
 #[cfg(test)]
 pub const STDOUT_FILENAME: &str = "logs/stdout_redirect.log";
 #[cfg(test)]
 pub const FILE_OUT_FILENAME: &str = "logs/file_out_redirect.log";
+#[cfg(test)]
+pub const STDERR_FILENAME: &str = "logs/stderr_redirect.log";
 
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -65,11 +70,25 @@ pub const FILE_OUT_FILENAME: &str = "logs/file_out_redirect.log";
 ///////////////////////////////////////////////////////////////////////////////
 
 pub struct Receiver {
-    logfile_prefix: &'static str,
-    logger_rx: mpsc::Receiver<Command>,
-    output_level: Level,
+    timestamp_format: String,
+    idle_timeout: Duration,
+    logger_rx: LoggerRx,
+    target_filter: TargetFilter,
+    shared_target_filter: Arc<Mutex<TargetFilter>>,
     output_stream: OutputStream,
+    sinks: Vec<SinkEntry>,
     msg_count: Arc<AtomicU64>,
+    #[cfg(target_os = "linux")]
+    reopen_handler: Option<ReopenHandler>,
+}
+
+#[cfg(test)]
+/// Test-only sink mirroring the output of a built-in sink into a verification file
+struct VerificationSink {
+    sink: Box<dyn Sink>,
+    format: RecordFormat,
+    colors: bool,
+    verf_filename: &'static str,
 }
 
 
@@ -79,20 +98,134 @@ pub struct Receiver {
 
 impl Receiver {
     /// Fully-qualified constructor
+    ///
+    /// Opens the logfile on the calling thread, so that any errors can be reported to the caller.
+    /// Any sinks registered with the builder are moved into the receiver.
     pub fn new(
-        logfile_prefix: &'static str,
-        logger_rx: mpsc::Receiver<Command>,
-        output_level: Level,
-        output_stream: OutputStream,
+        builder: &mut MtLoggerBuilder,
+        logger_rx: LoggerRx,
+        shared_target_filter: Arc<Mutex<TargetFilter>>,
         msg_count: Arc<AtomicU64>,
-    ) -> Self {
-        Self {
-            logfile_prefix,
+    ) -> io::Result<Self> {
+        let start_time = Local::now();
+        let logfile_naming = LogfileNaming::new(builder);
+        let (logfile_path, mut logfile) = logfile_naming.create(start_time)?;
+        if builder.append {
+            logfile.write_all(run_separator(builder, start_time).as_bytes())?;
+        }
+
+        // Failing to link the logfile or clean up after previous runs is not worth failing to log
+        // over, so only report these errors
+        if let Err(err) = logfile_naming.update_latest_link(&logfile_path) {
+            report_sink_error(
+                &builder.timestamp_format,
+                "link the logfile of",
+                SinkId::FILE,
+                err,
+            );
+        }
+        if let Some(retention) = Retention::new(builder, logfile_naming.clone()) {
+            if let Err(err) = retention.enforce(&logfile_path) {
+                report_sink_error(
+                    &builder.timestamp_format,
+                    "enforce the retention policy of",
+                    SinkId::FILE,
+                    err,
+                );
+            }
+        }
+
+        // Set up the built-in sinks, followed by any registered with the builder
+        let record_format = RecordFormat::new()
+            .timestamp_format(&builder.timestamp_format)
+            .thread_info(builder.thread_info)
+            .source_location(builder.source_location)
+            .module_path(builder.module_path);
+        let console_sink = ConsoleSink::new()
+            .format(record_format.clone())
+            .colors(builder.colors);
+        let stderr_sink = ConsoleSink::new()
+            .format(record_format.clone())
+            .colors(builder.colors)
+            .stderr(true);
+        let mut file_sink = FileSink::new(logfile)
+            .path(logfile_path.clone())
+            .format(record_format.clone())
+            .durability_policy(builder.durability_policy);
+        if builder.rotation_size.is_some() || builder.rotation_interval.is_some() {
+            file_sink = file_sink.rotation(FileRotation::new(
+                builder,
+                logfile_naming,
+                logfile_path,
+                start_time,
+            )?);
+        }
+        let sinks = vec![
+            SinkEntry {
+                id: SinkId::CONSOLE,
+                sink: Box::new(console_sink),
+                level_mask: builder.stdout_mask,
+            },
+            SinkEntry {
+                id: SinkId::FILE,
+                sink: Box::new(file_sink),
+                level_mask: builder.file_mask,
+            },
+            SinkEntry {
+                id: SinkId::STDERR,
+                sink: Box::new(stderr_sink),
+                level_mask: builder.stderr_mask,
+            },
+        ];
+
+        // Mirror the built-in sinks into the verification files read by the unit tests
+        #[cfg(test)]
+        let sinks: Vec<SinkEntry> = sinks
+            .into_iter()
+            .map(|entry| {
+                let (colors, verf_filename) = match entry.id {
+                    SinkId::CONSOLE => (builder.colors, STDOUT_FILENAME),
+                    SinkId::FILE => (false, FILE_OUT_FILENAME),
+                    _ => (builder.colors, STDERR_FILENAME),
+                };
+                SinkEntry {
+                    sink: Box::new(VerificationSink {
+                        sink: entry.sink,
+                        format: record_format.clone(),
+                        colors,
+                        verf_filename,
+                    }),
+                    ..entry
+                }
+            })
+            .collect();
+        let sinks = sinks.into_iter().chain(builder.sinks.drain(..)).collect();
+
+        // Wake often enough to honor the file sink's sync interval
+        let idle_timeout = match builder.durability_policy {
+            DurabilityPolicy::Interval(sync_interval) => sync_interval.min(DROP_REPORT_INTERVAL),
+            _ => DROP_REPORT_INTERVAL,
+        };
+
+        // The signal is only checked when the logging thread wakes, at least once per idle timeout
+        #[cfg(target_os = "linux")]
+        let reopen_handler = builder
+            .reopen_signal
+            .map(ReopenHandler::install)
+            .transpose()?;
+
+        Ok(Self {
+            timestamp_format: builder.timestamp_format.clone(),
+            idle_timeout,
             logger_rx,
-            output_level,
-            output_stream,
+            target_filter: TargetFilter::new(builder.output_mask, &builder.target_masks),
+            shared_target_filter,
+            output_stream: builder.output_stream,
+            sinks,
             msg_count,
-        }
+            #[cfg(target_os = "linux")]
+            reopen_handler,
+        })
     }
 
 
@@ -101,42 +234,23 @@ impl Receiver {
     \*  *  *  *  *  *  */
 
     /// Main loop for receiving logger commands
-    pub fn main(&mut self) {
-        let start_time = Local::now();
+    pub fn main(mut self) {
         println!(
             "{}: Entered LogReceiver thread.",
-            start_time.format(ENTRY_TIMESTAMP_FORMAT)
-        );
-
-        // Open a logfile, creating logs directory if necessary
-        let logfile_name = format!(
-            "{}_{}.log",
-            self.logfile_prefix,
-            start_time.format(FILE_TIMESTAMP_FORMAT)
+            Local::now().format(&self.timestamp_format)
         );
 
-        let mut path_buf = PathBuf::from(LOGFILE_DIR);
-        if !path_buf.as_path().exists() {
-            match fs::create_dir(path_buf.as_path()) {
-                Ok(()) => (),
-                Err(e) => panic!("Failed to create logs directory. Error: {}", e),
-            }
-        }
-
-        path_buf.push(logfile_name);
-        //OPT: *DESIGN* Could this be a member of Receiver?
-        let mut logfile = match fs::File::create(path_buf.as_path()) {
-            Ok(file) => file,
-            Err(err) => panic!(
-                "Failed to open logfile at {}. Error: {}",
-                path_buf.as_path().display(),
-                err
-            ),
-        };
-
         #[cfg(test)]
         {
             // Create verification files
+            fs::create_dir_all(PathBuf::from(STDOUT_FILENAME).parent().unwrap()).unwrap_or_else(
+                |err| {
+                    panic!(
+                        "Encountered error '{}' while creating verification file directory",
+                        err
+                    )
+                },
+            );
             fs::File::create(STDOUT_FILENAME).unwrap_or_else(|err| {
                 panic!(
                     "Encountered error '{}' while creating stdout verification file",
@@ -149,48 +263,76 @@ impl Receiver {
                     err
                 )
             });
+            fs::File::create(STDERR_FILENAME).unwrap_or_else(|err| {
+                panic!(
+                    "Encountered error '{}' while creating stderr verification file",
+                    err
+                )
+            });
         }
 
-        loop {
-            // Check the channel for commands
-            if let Ok(logger_cmd) = self.logger_rx.recv() {
-                // Handle command based on type
-                match logger_cmd {
-                    /* Messages */
-                    Command::LogMsg(log_tuple) => self.record_msg(&mut logfile, log_tuple),
-
-                    /* Configuration Commands */
-                    Command::SetOutputLevel(output_level) => {
-                        self.output_level = output_level;
-                    }
-                    Command::SetOutputStream(output_stream) => {
-                        self.output_stream = output_stream;
-                    }
+        let mut last_drop_report = Instant::now();
+        let shutdown_ack_tx = loop {
+            // Periodically report messages dropped due to a full channel
+            if last_drop_report.elapsed() >= DROP_REPORT_INTERVAL {
+                self.report_dropped_msgs();
+                last_drop_report = Instant::now();
+            }
+
+            // Reopen the logfiles if an external tool has asked us to
+            #[cfg(target_os = "linux")]
+            if self
+                .reopen_handler
+                .as_mut()
+                .is_some_and(ReopenHandler::take_reopen_request)
+            {
+                self.reopen_sinks();
+            }
 
-                    /* Flush */
-                    Command::Flush(flush_ack_tx) => {
-                        // If we're processing this command, all other previous commands have already
-                        // been processed. Simple send the ACK back to the main thread.
-
-                        // Only handle the failure case - nothing to do on success
-                        if let Err(e) = flush_ack_tx.send(()) {
-                            // Write an error into the log so we know something went wrong
-                            let err_tuple = MsgTuple {
-                                timestamp: Local::now(),
-                                level: Level::Error,
-                                fn_name: "LOG_RECEIVER_FLUSH_COMMAND".to_string(),
-                                line: line!(),
-                                msg: format!(
-                                    "Encountered SendError '{}' when sending flush ACK message.",
-                                    e
-                                ),
-                            };
-
-                            self.record_msg(&mut logfile, err_tuple);
-                        }
+            // Check the channel for commands, exiting if every sender has hung up
+            let logger_cmd = match self.logger_rx.try_recv() {
+                Ok(logger_cmd) => logger_cmd,
+                Err(TryRecvError::Empty) => {
+                    // Nothing else is queued, so let the sinks write out the current batch
+                    self.poll_sinks();
+
+                    match self.logger_rx.recv_timeout(self.idle_timeout) {
+                        Ok(logger_cmd) => logger_cmd,
+                        Err(RecvTimeoutError::Timeout) => continue,
+                        Err(RecvTimeoutError::Disconnected) => break None,
                     }
-                };
+                }
+                Err(TryRecvError::Disconnected) => break None,
+            };
+
+            // Stop once asked to shut down
+            if let Some(shutdown_ack_tx) = self.handle_cmd(logger_cmd) {
+                break Some(shutdown_ack_tx);
             }
+        };
+
+        // Handle any commands that were queued behind the shutdown command, so that their messages
+        // are recorded with the settings in effect when they were sent, and nobody waits on an ACK
+        let mut shutdown_ack_txs: Vec<_> = shutdown_ack_tx.into_iter().collect();
+        while let Ok(logger_cmd) = self.logger_rx.try_recv() {
+            shutdown_ack_txs.extend(self.handle_cmd(logger_cmd));
+        }
+        self.report_dropped_msgs();
+
+        // Hand the signal back to its previous handler once no other logger relies on it
+        #[cfg(target_os = "linux")]
+        drop(self.reopen_handler.take());
+
+        // Close every sink cleanly before acknowledging
+        for mut entry in self.sinks.drain(..) {
+            if let Err(err) = entry.sink.close() {
+                report_sink_error(&self.timestamp_format, "close", entry.id, err);
+            }
+        }
+
+        // Nothing to do if the ACK fails, the caller has already given up on us
+        for shutdown_ack_tx in shutdown_ack_txs {
+            shutdown_ack_tx.send(()).ok();
         }
     }
 
@@ -199,87 +341,178 @@ impl Receiver {
      * Helper Methods *
     \*  *  *  *  *  *  */
 
-    fn record_msg(&mut self, logfile: &mut File, log_tuple: MsgTuple) {
-        // Format the timestamp for recording
-        let formatted_timestamp = log_tuple.timestamp.format(ENTRY_TIMESTAMP_FORMAT);
-
-        if log_tuple.level >= self.output_level {
-            // Console output
-            if self.output_stream as u8 & OutputStream::StdOut as u8 != 0 {
-                let log_color = match log_tuple.level {
-                    Level::Trace => "\x1b[030;105m",
-                    Level::Debug => "\x1b[030;106m",
-                    Level::Info => "\x1b[030;107m",
-                    Level::Warning => "\x1b[030;103m",
-                    Level::Error => "\x1b[030;101m",
-                    Level::Fatal => "\x1b[031;040m",
-                };
-                let msg_formatted = format!(
-                    "{timestamp}: {color_set}[{level:^level_width$}]\x1b[0m {fn_name}() line {line}:\n{msg:>msg_leftpad$}",
-                    timestamp   = formatted_timestamp,
-                    color_set   = log_color,
-                    level       = log_tuple.level.to_string(),
-                    level_width = LEVEL_LABEL_WIDTH,
-                    fn_name     = log_tuple.fn_name,
-                    line        = log_tuple.line,
-                    msg         = log_tuple.msg,
-                    msg_leftpad = MESSAGE_LEFT_PADDING + log_tuple.msg.len(),
-                );
+    /// Handles a single command, returning the ACK sender if it asks the logging thread to shut
+    /// down
+    fn handle_cmd(&mut self, logger_cmd: Command) -> Option<mpsc::Sender<()>> {
+        match logger_cmd {
+            /* Messages */
+            Command::LogMsg(log_tuple) => self.record_msg(*log_tuple),
+
+            /* Configuration Commands */
+            // The sending side applies filter updates before queueing them, so take its filter
+            // as is rather than applying updates that may arrive out of order
+            Command::SetOutputLevel(_)
+            | Command::SetLevelMask(_)
+            | Command::SetTargetLevel(..)
+            | Command::SetTargetLevelMask(..) => {
+                self.target_filter = self
+                    .shared_target_filter
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .clone();
+            }
+            Command::SetOutputStream(output_stream) => {
+                self.output_stream = output_stream;
+            }
+            Command::SetStreamLevel(output_stream, output_level) => {
+                self.set_stream_mask(output_stream, LevelMask::at_or_above(output_level));
+            }
+            Command::SetStreamLevelMask(output_stream, output_mask) => {
+                self.set_stream_mask(output_stream, output_mask);
+            }
+            Command::SetSinkLevelMask(sink_id, output_mask) => {
+                self.set_sink_mask(sink_id, output_mask);
+            }
 
-                // Write to console
-                println!("{}", msg_formatted);
+            /* Sink Commands */
+            Command::AddSink(sink_id, sink) => {
+                // Replace any sink already registered with this ID
+                self.remove_sink(sink_id);
+                self.sinks.push(SinkEntry {
+                    id: sink_id,
+                    sink,
+                    level_mask: LevelMask::all(),
+                });
+            }
+            Command::RemoveSink(sink_id) => self.remove_sink(sink_id),
+            Command::ReopenFiles => self.reopen_sinks(),
+
+            /* Flush */
+            Command::Flush(flush_ack_tx) => {
+                // If we're processing this command, all other previous commands have already
+                // been processed. Report any drops so they are visible after the flush, write
+                // everything out, then send the ACK back to the main thread.
+                self.report_dropped_msgs();
+                for entry in &mut self.sinks {
+                    if let Err(err) = entry.sink.flush() {
+                        report_sink_error(&self.timestamp_format, "flush", entry.id, err);
+                    }
+                }
 
-                #[cfg(test)]
-                {
-                    // Add newline to formatted message for readability
-                    let writeable_msg = format!("{}\n", msg_formatted);
-
-                    // Write to stdout verification file
-                    let mut stdout_redirect = fs::OpenOptions::new().append(true).open(STDOUT_FILENAME)
-                        .unwrap_or_else(
-                            |err| panic!("Encountered error '{}' while attempting to open stdout verification file.", err)
-                        );
-                    stdout_redirect.write_all(writeable_msg.as_bytes())
-                        .unwrap_or_else(
-                            |err| panic!("Encountered error '{}' while attempting to write to stdout verification file.", err)
-                        );
+                // Only handle the failure case - nothing to do on success
+                if let Err(e) = flush_ack_tx.send(()) {
+                    // Write an error into the log so we know something went wrong
+                    let err_tuple = MsgTuple {
+                        timestamp: Local::now(),
+                        level: Level::Error,
+                        target: module_path!().to_string(),
+                        fn_name: "LOG_RECEIVER_FLUSH_COMMAND".to_string(),
+                        line: line!(),
+                        column: column!(),
+                        file: file!(),
+                        module_path: module_path!(),
+                        thread_name: thread::current().name().map(String::from),
+                        thread_id: thread::current().id(),
+                        msg: format!(
+                            "Encountered SendError '{}' when sending flush ACK message.",
+                            e
+                        ),
+                        fields: Vec::new(),
+                    };
+
+                    self.record_msg(err_tuple);
                 }
             }
 
-            // File output
-            if self.output_stream as u8 & OutputStream::File as u8 != 0 {
-                let msg_formatted = format!(
-                    "{timestamp}: [{level:^level_width$}] {fn_name}() line {line}:\n{msg:>msg_leftpad$}\n",
-                    timestamp   = formatted_timestamp,
-                    level       = log_tuple.level.to_string(),
-                    level_width = LEVEL_LABEL_WIDTH,
-                    fn_name     = log_tuple.fn_name,
-                    line        = log_tuple.line,
-                    msg         = log_tuple.msg,
-                    msg_leftpad = MESSAGE_LEFT_PADDING + log_tuple.msg.len(),
-                );
+            /* Shutdown */
+            Command::Shutdown(shutdown_ack_tx) => return Some(shutdown_ack_tx),
+        };
+
+        None
+    }
+
+    /// Lets every sink perform its periodic work, e.g., writing out buffered output
+    fn poll_sinks(&mut self) {
+        for entry in &mut self.sinks {
+            if let Err(err) = entry.sink.poll() {
+                report_sink_error(&self.timestamp_format, "poll", entry.id, err);
+            }
+        }
+    }
+
+    /// Closes and removes the sink with the given ID, if any
+    fn remove_sink(&mut self, sink_id: SinkId) {
+        if let Some(idx) = self.sinks.iter().position(|entry| entry.id == sink_id) {
+            let mut entry = self.sinks.remove(idx);
+            if let Err(err) = entry.sink.close() {
+                report_sink_error(&self.timestamp_format, "close", entry.id, err);
+            }
+        }
+    }
+
+    /// Has every sink reopen its files, e.g., after they were renamed by an external tool
+    fn reopen_sinks(&mut self) {
+        for entry in &mut self.sinks {
+            if let Err(err) = entry.sink.reopen() {
+                report_sink_error(&self.timestamp_format, "reopen", entry.id, err);
+            }
+        }
+    }
+
+    /// Sets the level mask of the sink with the given ID, if any
+    fn set_sink_mask(&mut self, sink_id: SinkId, output_mask: LevelMask) {
+        if let Some(entry) = self.sinks.iter_mut().find(|entry| entry.id == sink_id) {
+            entry.level_mask = output_mask;
+        }
+    }
+
+    /// Sets the level mask of the built-in sink of each of the given streams
+    fn set_stream_mask(&mut self, output_stream: OutputStream, output_mask: LevelMask) {
+        for sink_id in output_stream.sink_ids() {
+            self.set_sink_mask(sink_id, output_mask);
+        }
+    }
 
-                //FEAT: Avoid spewing the same error if a file explodes or something
-                logfile
-                    .write_all(msg_formatted.as_bytes())
-                    .unwrap_or_else(|err| {
-                        eprintln!(
-                            "{}: Encountered error '{}' while attempting to write to log file.",
-                            log_tuple.timestamp, err
-                        )
-                    });
-
-                #[cfg(test)]
+    /// Records a warning if any messages have been dropped since the last report
+    fn report_dropped_msgs(&mut self) {
+        let dropped_count = self.logger_rx.take_dropped_count();
+        if dropped_count > 0 {
+            let warning_tuple = MsgTuple {
+                timestamp: Local::now(),
+                level: Level::Warning,
+                target: module_path!().to_string(),
+                fn_name: "LOG_RECEIVER_OVERFLOW".to_string(),
+                line: line!(),
+                column: column!(),
+                file: file!(),
+                module_path: module_path!(),
+                thread_name: thread::current().name().map(String::from),
+                thread_id: thread::current().id(),
+                msg: format!(
+                    "Dropped {} message(s) due to a full logging channel.",
+                    dropped_count
+                ),
+                fields: Vec::new(),
+            };
+
+            self.record_msg(warning_tuple);
+        }
+    }
+
+    fn record_msg(&mut self, log_tuple: MsgTuple) {
+        if self
+            .target_filter
+            .mask_for(&log_tuple.target, &log_tuple.fn_name)
+            .contains(log_tuple.level)
+        {
+            for entry in &mut self.sinks {
+                // The built-in sinks are also controlled by the active output stream
+                if self.output_stream.writes_to(entry.id, log_tuple.level)
+                    && entry.level_mask.contains(log_tuple.level)
                 {
-                    // Write to stdout verification file
-                    let mut file_redirect = fs::OpenOptions::new().append(true).open(FILE_OUT_FILENAME)
-                        .unwrap_or_else(
-                            |err| panic!("Encountered error '{}' while attempting to open file output verification file.", err)
-                        );
-                    file_redirect.write_all(msg_formatted.as_bytes())
-                        .unwrap_or_else(
-                            |err| panic!("Encountered error '{}' while attempting to write to file output verification file.", err)
-                        )
+                    if let Err(err) = entry.sink.record(&log_tuple) {
+                        report_sink_error(&self.timestamp_format, "record to", entry.id, err);
+                    }
                 }
             }
         }
@@ -288,3 +521,89 @@ impl Receiver {
         self.msg_count.fetch_add(1, Ordering::SeqCst);
     }
 }
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Static Functions
+///////////////////////////////////////////////////////////////////////////////
+
+/// Returns the header marking the start of a run in a logfile opened in append mode
+fn run_separator(builder: &MtLoggerBuilder, start_time: DateTime<Local>) -> String {
+    format!(
+        "{separator}\n{timestamp}: Started new run of '{prefix}' with PID {pid}\n{separator}\n",
+        separator = RUN_SEPARATOR,
+        timestamp = start_time.format(&builder.timestamp_format),
+        prefix = builder.logfile_prefix,
+        pid = process::id(),
+    )
+}
+
+/// Reports an error returned by a sink on stderr
+fn report_sink_error(timestamp_format: &str, action: &str, sink_id: SinkId, err: io::Error) {
+    eprintln!(
+        "{}: Encountered error '{}' while attempting to {} {}.",
+        Local::now().format(timestamp_format),
+        err,
+        action,
+        sink_id
+    );
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Trait Implementations
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+impl Sink for VerificationSink {
+    fn record(&mut self, log_tuple: &MsgTuple) -> io::Result<()> {
+        // The message may still have been written if the sink reports an error, e.g., the file
+        // sink failing to rotate
+        let record_result = self.sink.record(log_tuple);
+
+        let msg_formatted = if self.colors {
+            self.format.format_colored(log_tuple)
+        } else {
+            self.format.format(log_tuple)
+        };
+
+        // Add newline to formatted message for readability
+        let writeable_msg = format!("{}\n", msg_formatted);
+
+        let mut verf_file = fs::OpenOptions::new()
+            .append(true)
+            .open(self.verf_filename)
+            .unwrap_or_else(|err| {
+                panic!(
+                    "Encountered error '{}' while attempting to open verification file '{}'.",
+                    err, self.verf_filename
+                )
+            });
+        verf_file
+            .write_all(writeable_msg.as_bytes())
+            .unwrap_or_else(|err| {
+                panic!(
+                    "Encountered error '{}' while attempting to write to verification file '{}'.",
+                    err, self.verf_filename
+                )
+            });
+
+        record_result
+    }
+
+    fn poll(&mut self) -> io::Result<()> {
+        self.sink.poll()
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.sink.flush()
+    }
+
+    fn reopen(&mut self) -> io::Result<()> {
+        self.sink.reopen()
+    }
+
+    fn close(&mut self) -> io::Result<()> {
+        self.sink.close()
+    }
+}
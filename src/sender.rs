@@ -23,6 +23,7 @@ Purpose:
 
 use std::sync::mpsc;
 
+use crate::channel::LoggerTx;
 use crate::Command;
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -31,7 +32,7 @@ use crate::Command;
 
 #[derive(Clone, Debug)]
 pub struct Sender {
-    logger_tx: mpsc::SyncSender<Command>,
+    logger_tx: LoggerTx,
 }
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -40,7 +41,7 @@ pub struct Sender {
 
 impl Sender {
     /// Fully-qualified constructor
-    pub fn new(logger_tx: mpsc::SyncSender<Command>) -> Self {
+    pub fn new(logger_tx: LoggerTx) -> Self {
         Self { logger_tx }
     }
 
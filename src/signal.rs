@@ -0,0 +1,180 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : signal.rs
+
+Copyright (C) 2021 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module installs the handler for the signals requesting that logfiles
+    be reopened, e.g., by logrotate. The handler only counts the signals it
+    receives, which the logging thread checks whenever it wakes. The previous
+    disposition of a signal is restored once no logger relies on it anymore.
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use std::io;
+use std::mem;
+use std::ptr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+use libc::{c_int, sighandler_t, SIGHUP, SIGUSR1};
+
+use crate::ReopenSignal;
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Named Constants
+///////////////////////////////////////////////////////////////////////////////
+
+/// Number of times each reopen signal has been received, indexed by `signal_index()`
+static SIGNAL_COUNTS: [AtomicU64; 2] = [AtomicU64::new(0), AtomicU64::new(0)];
+
+/// Signals the handler is installed for, shared between every logger reopening on them
+static INSTALLATIONS: Mutex<Vec<Installation>> = Mutex::new(Vec::new());
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+/// Registration of a logger with the handler for a signal, which is uninstalled when the last
+/// registration is dropped
+#[derive(Debug)]
+pub struct ReopenHandler {
+    signum: c_int,
+    seen_count: u64,
+}
+
+/// Handler installed for a signal, along with the disposition it replaced
+struct Installation {
+    signum: c_int,
+    handler_count: usize,
+    previous_action: libc::sigaction,
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Object Implementation
+///////////////////////////////////////////////////////////////////////////////
+
+impl ReopenHandler {
+    /// Installs the handler for the given signal unless it already is, saving the previous
+    /// disposition of the signal
+    pub fn install(reopen_signal: ReopenSignal) -> io::Result<Self> {
+        let signum = match reopen_signal {
+            ReopenSignal::Hangup => SIGHUP,
+            ReopenSignal::User1 => SIGUSR1,
+        };
+
+        let mut installations = INSTALLATIONS.lock().unwrap_or_else(PoisonError::into_inner);
+        match installations
+            .iter_mut()
+            .find(|installation| installation.signum == signum)
+        {
+            Some(installation) => installation.handler_count += 1,
+            None => {
+                // SAFETY: Both structs are plain data, for which all-zero is a valid value, and
+                // the handler only increments an atomic, which is async-signal-safe
+                let previous_action = unsafe {
+                    let mut action: libc::sigaction = mem::zeroed();
+                    action.sa_sigaction = handle_signal as extern "C" fn(c_int) as sighandler_t;
+                    action.sa_flags = libc::SA_RESTART;
+                    libc::sigemptyset(&mut action.sa_mask);
+
+                    let mut previous_action: libc::sigaction = mem::zeroed();
+                    if libc::sigaction(signum, &action, &mut previous_action) != 0 {
+                        return Err(io::Error::last_os_error());
+                    }
+                    previous_action
+                };
+
+                installations.push(Installation {
+                    signum,
+                    handler_count: 1,
+                    previous_action,
+                });
+            }
+        }
+
+        Ok(Self {
+            signum,
+            seen_count: SIGNAL_COUNTS[signal_index(signum)].load(Ordering::Relaxed),
+        })
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Utility Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Returns whether the signal has been received since the last call
+    pub fn take_reopen_request(&mut self) -> bool {
+        let signal_count = SIGNAL_COUNTS[signal_index(self.signum)].load(Ordering::Relaxed);
+        if signal_count == self.seen_count {
+            return false;
+        }
+
+        self.seen_count = signal_count;
+        true
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Static Functions
+///////////////////////////////////////////////////////////////////////////////
+
+/// Returns the index of the given reopen signal in `SIGNAL_COUNTS`
+fn signal_index(signum: c_int) -> usize {
+    match signum {
+        SIGHUP => 0,
+        _ => 1,
+    }
+}
+
+extern "C" fn handle_signal(signum: c_int) {
+    SIGNAL_COUNTS[signal_index(signum)].fetch_add(1, Ordering::Relaxed);
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Trait Implementations
+///////////////////////////////////////////////////////////////////////////////
+
+impl Drop for ReopenHandler {
+    fn drop(&mut self) {
+        let mut installations = INSTALLATIONS.lock().unwrap_or_else(PoisonError::into_inner);
+        let idx = match installations
+            .iter()
+            .position(|installation| installation.signum == self.signum)
+        {
+            Some(idx) => idx,
+            None => return,
+        };
+
+        installations[idx].handler_count -= 1;
+        if installations[idx].handler_count == 0 {
+            let installation = installations.remove(idx);
+            // SAFETY: The previous action was filled in by sigaction() itself. Nothing useful can
+            // be done if restoring it fails.
+            unsafe {
+                libc::sigaction(
+                    installation.signum,
+                    &installation.previous_action,
+                    ptr::null_mut(),
+                );
+            }
+        }
+    }
+}
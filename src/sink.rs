@@ -0,0 +1,573 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : sink.rs
+
+Copyright (C) 2021 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module defines the Sink trait, which is implemented by every
+    destination the logging thread writes messages to, along with the built-in
+    Console and File sinks and the Record Format they share.
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, prelude::*, BufWriter};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+use chrono::{DateTime, Local};
+
+use crate::logfile::{self, FileRotation};
+use crate::{DurabilityPolicy, Level, LevelMask, MsgTuple};
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Named Constants
+///////////////////////////////////////////////////////////////////////////////
+
+/// Default format string for timestamps
+pub(crate) const ENTRY_TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%T%.9f";
+
+/// Padding required to align text after Level label
+const LEVEL_LABEL_WIDTH: usize = 9;
+
+/// Padding to the left of the log message
+const MESSAGE_LEFT_PADDING: usize = 3;
+
+/// ANSI escape sequence resetting the console color
+const COLOR_RESET: &str = "\x1b[0m";
+
+/// Source of unique IDs for sinks, after those reserved for the built-in sinks
+static NEXT_SINK_ID: AtomicU64 = AtomicU64::new(3);
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+/// Destination for recorded log messages.
+///
+/// Sinks are owned by the logging thread, which calls them in the order messages were logged.
+/// Any errors returned are reported on stderr by the logging thread.
+///
+/// # Examples
+///
+/// Collect the content of `Error`-level messages and higher in memory.
+/// ```
+/// # use std::io;
+/// # use std::sync::{Arc, Mutex};
+/// # use mt_logger::{Level, MsgTuple, Sink};
+/// struct ErrorCollector {
+///     errors: Arc<Mutex<Vec<String>>>,
+/// }
+///
+/// impl Sink for ErrorCollector {
+///     fn record(&mut self, log_tuple: &MsgTuple) -> io::Result<()> {
+///         if log_tuple.level >= Level::Error {
+///             self.errors.lock().unwrap().push(log_tuple.msg.clone());
+///         }
+///         Ok(())
+///     }
+/// }
+/// ```
+pub trait Sink: Send {
+    /// Records a message that passed the logger's level filters
+    fn record(&mut self, log_tuple: &MsgTuple) -> io::Result<()>;
+
+    /// Called whenever the logging thread runs out of queued messages, and periodically while it
+    /// is idle, e.g., to write out buffered output. Does nothing by default.
+    fn poll(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Ensures every recorded message has been written out, called by [`mt_flush!`].
+    /// Does nothing by default.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Reopens any files the sink writes to, called by [`mt_reopen_files!`], e.g., after they
+    /// were renamed by an external tool. Does nothing by default.
+    fn reopen(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Flushes the sink and releases its resources, called when the sink is removed or the
+    /// logger is shut down. The sink is dropped afterwards. Calls [`Sink::flush`] by default.
+    fn close(&mut self) -> io::Result<()> {
+        self.flush()
+    }
+}
+
+/// Identifies a sink registered with a logger, so that it can be configured or removed later.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SinkId(u64);
+
+/// Layout of the built-in two-line message format, shared by the built-in sinks.
+///
+/// Messages are formatted as a header containing the timestamp, level, function name, line
+/// number, and any enabled details, followed by the indented message content and fields.
+#[derive(Clone, Debug)]
+pub struct RecordFormat {
+    timestamp_format: String,
+    thread_info: bool,
+    source_location: bool,
+    module_path: bool,
+}
+
+/// Built-in sink writing messages to StdOut or StdErr, with optional ANSI colors.
+#[derive(Clone, Debug)]
+pub struct ConsoleSink {
+    format: RecordFormat,
+    colors: bool,
+    stderr: bool,
+}
+
+/// Built-in sink writing messages to a file, buffering writes between batches.
+#[derive(Debug)]
+pub struct FileSink {
+    logfile: BufWriter<File>,
+    path: Option<PathBuf>,
+    rotation: Option<FileRotation>,
+    format: RecordFormat,
+    durability_policy: DurabilityPolicy,
+    last_sync: Instant,
+    unsynced_writes: bool,
+}
+
+/// Sink registered with the logging thread, along with the levels it records
+pub(crate) struct SinkEntry {
+    pub id: SinkId,
+    pub sink: Box<dyn Sink>,
+    pub level_mask: LevelMask,
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Object Implementation
+///////////////////////////////////////////////////////////////////////////////
+
+impl SinkId {
+    /// ID of the built-in StdOut console sink, controlled by `OutputStream::StdOut` and
+    /// `OutputStream::Split`
+    pub const CONSOLE: Self = Self(0);
+
+    /// ID of the built-in file sink, controlled by `OutputStream::File`
+    pub const FILE: Self = Self(1);
+
+    /// ID of the built-in StdErr console sink, controlled by `OutputStream::StdErr` and
+    /// `OutputStream::Split`
+    pub const STDERR: Self = Self(2);
+
+    /// Returns an ID that is unique within this process
+    pub fn unique() -> Self {
+        Self(NEXT_SINK_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl RecordFormat {
+    /// Creates a format with the default timestamp format and no optional details
+    pub fn new() -> Self {
+        Self {
+            timestamp_format: ENTRY_TIMESTAMP_FORMAT.to_string(),
+            thread_info: false,
+            source_location: false,
+            module_path: false,
+        }
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Builder Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Sets the [`chrono` format string](chrono::format::strftime) used for message timestamps.
+    /// Defaults to `%Y-%m-%dT%T%.9f`.
+    pub fn timestamp_format(mut self, timestamp_format: &str) -> Self {
+        self.timestamp_format = timestamp_format.to_string();
+        self
+    }
+
+    /// Enables or disables rendering the name and ID of the thread that logged each message.
+    /// Defaults to `false`.
+    pub fn thread_info(mut self, thread_info: bool) -> Self {
+        self.thread_info = thread_info;
+        self
+    }
+
+    /// Enables or disables rendering the source file, line, and column of each message.
+    /// Defaults to `false`.
+    pub fn source_location(mut self, source_location: bool) -> Self {
+        self.source_location = source_location;
+        self
+    }
+
+    /// Enables or disables rendering the module path of each message. Defaults to `false`.
+    pub fn module_path(mut self, module_path: bool) -> Self {
+        self.module_path = module_path;
+        self
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Utility Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Formats the message without a trailing newline
+    pub fn format(&self, log_tuple: &MsgTuple) -> String {
+        self.format_with_colors(log_tuple, "", "")
+    }
+
+    /// Formats the message without a trailing newline, coloring the level label with ANSI
+    /// escape sequences
+    pub fn format_colored(&self, log_tuple: &MsgTuple) -> String {
+        let log_color = match log_tuple.level {
+            Level::Trace => "\x1b[030;105m",
+            Level::Debug => "\x1b[030;106m",
+            Level::Info => "\x1b[030;107m",
+            Level::Warning => "\x1b[030;103m",
+            Level::Error => "\x1b[030;101m",
+            Level::Fatal => "\x1b[031;040m",
+        };
+
+        self.format_with_colors(log_tuple, log_color, COLOR_RESET)
+    }
+
+
+    /*  *  *  *  *  *  *\
+     * Helper Methods *
+    \*  *  *  *  *  *  */
+
+    fn format_with_colors(
+        &self,
+        log_tuple: &MsgTuple,
+        color_set: &str,
+        color_reset: &str,
+    ) -> String {
+        // Append any structured fields to the message content
+        let mut msg_content = log_tuple.msg.clone();
+        for (key, value) in &log_tuple.fields {
+            msg_content.push_str(&format!(" {}={}", key, value));
+        }
+
+        // Collect any optional details to be appended to the header
+        let mut header_details = String::new();
+        if self.thread_info {
+            match &log_tuple.thread_name {
+                Some(thread_name) => header_details.push_str(&format!(
+                    " on thread '{}' ({:?})",
+                    thread_name, log_tuple.thread_id
+                )),
+                None => header_details
+                    .push_str(&format!(" on unnamed thread ({:?})", log_tuple.thread_id)),
+            }
+        }
+        if self.source_location && !log_tuple.file.is_empty() {
+            header_details.push_str(&format!(
+                " at {}:{}:{}",
+                log_tuple.file, log_tuple.line, log_tuple.column
+            ));
+        }
+        if self.module_path && !log_tuple.module_path.is_empty() {
+            header_details.push_str(&format!(" in {}", log_tuple.module_path));
+        }
+
+        format!(
+            "{timestamp}: {color_set}[{level:^level_width$}]{color_reset} {fn_name}() line {line}{details}:\n{msg:>msg_leftpad$}",
+            timestamp   = log_tuple.timestamp.format(&self.timestamp_format),
+            color_set   = color_set,
+            color_reset = color_reset,
+            level       = log_tuple.level.to_string(),
+            level_width = LEVEL_LABEL_WIDTH,
+            fn_name     = log_tuple.fn_name,
+            line        = log_tuple.line,
+            details     = header_details,
+            msg         = msg_content,
+            msg_leftpad = MESSAGE_LEFT_PADDING + msg_content.len(),
+        )
+    }
+}
+
+impl ConsoleSink {
+    /// Creates a console sink writing to StdOut, with the default format and colors enabled
+    pub fn new() -> Self {
+        Self {
+            format: RecordFormat::new(),
+            colors: true,
+            stderr: false,
+        }
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Builder Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Sets the [`RecordFormat`] used for messages
+    pub fn format(mut self, format: RecordFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Enables or disables ANSI colors. Defaults to `true`.
+    pub fn colors(mut self, colors: bool) -> Self {
+        self.colors = colors;
+        self
+    }
+
+    /// Writes messages to StdErr rather than StdOut. Defaults to `false`.
+    pub fn stderr(mut self, stderr: bool) -> Self {
+        self.stderr = stderr;
+        self
+    }
+}
+
+impl FileSink {
+    /// Creates a file sink writing to the given file, with the default format and a durability
+    /// policy of `DurabilityPolicy::Never`
+    pub fn new(logfile: File) -> Self {
+        Self {
+            logfile: BufWriter::new(logfile),
+            path: None,
+            rotation: None,
+            format: RecordFormat::new(),
+            durability_policy: DurabilityPolicy::Never,
+            last_sync: Instant::now(),
+            unsynced_writes: false,
+        }
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Builder Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Sets the [`RecordFormat`] used for messages
+    pub fn format(mut self, format: RecordFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Sets the [`DurabilityPolicy`] determining when the file is synced to disk.
+    /// Defaults to `DurabilityPolicy::Never`.
+    pub fn durability_policy(mut self, durability_policy: DurabilityPolicy) -> Self {
+        self.durability_policy = durability_policy;
+        self
+    }
+
+    /// Sets the path the logfile is reopened at by [`Sink::reopen`]. Without one, and without
+    /// rotation, the logfile is never reopened.
+    pub(crate) fn path(mut self, path: PathBuf) -> Self {
+        self.path = Some(path);
+        self
+    }
+
+    /// Rotates the logfile according to the given settings
+    pub(crate) fn rotation(mut self, rotation: FileRotation) -> Self {
+        self.rotation = Some(rotation);
+        self
+    }
+
+
+    /*  *  *  *  *  *  *\
+     * Helper Methods *
+    \*  *  *  *  *  *  */
+
+    /// Closes the logfile and continues writing to a new one, as determined by the rotation
+    /// settings
+    fn rotate(&mut self, rotation_time: DateTime<Local>) -> io::Result<()> {
+        if let Some(rotation) = &mut self.rotation {
+            self.logfile.flush()?;
+            if self.durability_policy != DurabilityPolicy::Never {
+                self.logfile.get_ref().sync_all()?;
+            }
+
+            self.logfile = BufWriter::new(rotation.rotate(rotation_time)?);
+            self.last_sync = Instant::now();
+            self.unsynced_writes = false;
+
+            let link_result = rotation.update_latest_link();
+            rotation.enforce_retention()?;
+            link_result?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes any buffered output to the file, and waits for it to reach the disk
+    fn sync(&mut self) -> io::Result<()> {
+        self.logfile.flush()?;
+        self.logfile.get_ref().sync_data()?;
+
+        self.last_sync = Instant::now();
+        self.unsynced_writes = false;
+        Ok(())
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Trait Implementations
+///////////////////////////////////////////////////////////////////////////////
+
+impl Default for RecordFormat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Default for ConsoleSink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for SinkId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::CONSOLE => write!(f, "console sink"),
+            Self::FILE => write!(f, "file sink"),
+            Self::STDERR => write!(f, "stderr sink"),
+            Self(id) => write!(f, "sink #{}", id),
+        }
+    }
+}
+
+impl fmt::Debug for SinkEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SinkEntry")
+            .field("id", &self.id)
+            .field("level_mask", &self.level_mask)
+            .finish()
+    }
+}
+
+
+/*  *  *  *  *  *  *  *\
+ *    ConsoleSink     *
+\*  *  *  *  *  *  *  */
+
+impl Sink for ConsoleSink {
+    fn record(&mut self, log_tuple: &MsgTuple) -> io::Result<()> {
+        let msg_formatted = if self.colors {
+            self.format.format_colored(log_tuple)
+        } else {
+            self.format.format(log_tuple)
+        };
+
+        // Write to console through the print macros, so that the test harness captures the output
+        if self.stderr {
+            eprintln!("{}", msg_formatted);
+        } else {
+            println!("{}", msg_formatted);
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.stderr {
+            io::stderr().flush()
+        } else {
+            io::stdout().flush()
+        }
+    }
+}
+
+
+/*  *  *  *  *  *  *  *\
+ *      FileSink      *
+\*  *  *  *  *  *  *  */
+
+impl Sink for FileSink {
+    fn record(&mut self, log_tuple: &MsgTuple) -> io::Result<()> {
+        let msg_formatted = format!("{}\n", self.format.format(log_tuple));
+        let msg_len = msg_formatted.len() as u64;
+
+        // Rotate before writing, so that messages are never split across logfiles. If rotation
+        // fails, keep writing to the current logfile rather than losing the message.
+        let rotate_result = match &self.rotation {
+            Some(rotation) if rotation.is_due(msg_len, log_tuple.timestamp) => {
+                self.rotate(log_tuple.timestamp)
+            }
+            _ => Ok(()),
+        };
+
+        //FEAT: Avoid spewing the same error if a file explodes or something
+        self.logfile.write_all(msg_formatted.as_bytes())?;
+        if let Some(rotation) = &mut self.rotation {
+            rotation.add_written(msg_len);
+        }
+
+        // Apply the durability policy
+        self.unsynced_writes = true;
+        match self.durability_policy {
+            DurabilityPolicy::EveryMessage => self.sync()?,
+            DurabilityPolicy::OnError if log_tuple.level >= Level::Error => self.sync()?,
+            DurabilityPolicy::Interval(sync_interval)
+                if self.last_sync.elapsed() >= sync_interval =>
+            {
+                self.sync()?
+            }
+            _ => (),
+        }
+
+        rotate_result
+    }
+
+    fn poll(&mut self) -> io::Result<()> {
+        // Write out the current batch, and sync if the durability policy's interval has elapsed
+        match self.durability_policy {
+            DurabilityPolicy::Interval(sync_interval)
+                if self.unsynced_writes && self.last_sync.elapsed() >= sync_interval =>
+            {
+                self.sync()
+            }
+            _ => self.logfile.flush(),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.durability_policy {
+            DurabilityPolicy::Never => self.logfile.flush(),
+            _ => self.sync(),
+        }
+    }
+
+    fn reopen(&mut self) -> io::Result<()> {
+        self.logfile.flush()?;
+        if self.durability_policy != DurabilityPolicy::Never {
+            self.logfile.get_ref().sync_all()?;
+        }
+
+        // The rotation settings track the active logfile's path and size themselves
+        let logfile = match (&mut self.rotation, &self.path) {
+            (Some(rotation), _) => rotation.reopen()?,
+            (None, Some(path)) => logfile::open_logfile(path, true)?,
+            (None, None) => return Ok(()),
+        };
+        self.logfile = BufWriter::new(logfile);
+        self.last_sync = Instant::now();
+        self.unsynced_writes = false;
+
+        Ok(())
+    }
+
+    fn close(&mut self) -> io::Result<()> {
+        self.logfile.flush()?;
+        self.logfile.get_ref().sync_all()
+    }
+}